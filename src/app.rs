@@ -11,7 +11,9 @@ use crate::{
   action::Action,
   components::{fps::FpsCounter, home::Home, Component},
   config::Config,
-  repository::{self, local_storage::LocalStorageRepository},
+  repository::{
+    self, cookies::CookieRepository, history::HistoryRepository, local_storage::LocalStorageRepository,
+  },
   tui,
 };
 
@@ -36,7 +38,9 @@ pub struct App {
 impl App {
   pub fn new(tick_rate: f64, frame_rate: f64) -> Result<Self> {
     let repo = LocalStorageRepository::default();
-    let home = Home::new(Arc::new(Mutex::new(repo)));
+    let history = HistoryRepository::new();
+    let cookies = CookieRepository::new();
+    let home = Home::new(Arc::new(Mutex::new(repo)), Arc::new(Mutex::new(history)), Arc::new(Mutex::new(cookies)));
     let fps = FpsCounter::new();
     let config = Config::new()?;
     let mode = Mode::Home;
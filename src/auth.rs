@@ -0,0 +1,225 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::Utc;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+
+use crate::http::{self, replace_env_variables, Context, PreparedRequest, PLACEHOLDER};
+
+pub mod digest;
+pub mod hmac_signature;
+pub mod oauth2;
+pub mod pkce;
+pub mod sigv4;
+
+/// What saved credentials are replaced with.
+pub const REDACTED: &str = "[redacted]";
+
+/// Where an auth puts its credentials, besides the headers that always carry credentials, so they can be left out of
+/// what is saved.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Secrets {
+  /// Lowercase header names
+  pub headers: Vec<String>,
+  pub query: Vec<String>,
+}
+
+/// How a request authenticates. Every field accepts `{{env}}` variables.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Auth {
+  /// Sends no credentials, even when the collection has a default auth
+  None,
+  Basic {
+    username: String,
+    password: String,
+  },
+  Bearer {
+    token: String,
+  },
+  ApiKey {
+    key: String,
+    value: String,
+    #[serde(default)]
+    location: ApiKeyLocation,
+  },
+  #[serde(rename = "oauth2")]
+  OAuth2(oauth2::OAuth2),
+  #[serde(rename = "aws_sigv4")]
+  AwsSigV4(sigv4::AwsSigV4),
+  Hmac(hmac_signature::HmacSignature),
+  /// Answers the server's 401 challenge and sends the request again
+  Digest(digest::DigestAuth),
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiKeyLocation {
+  #[default]
+  Header,
+  Query,
+}
+
+impl Auth {
+  pub fn name(&self) -> &'static str {
+    match self {
+      Auth::None => "none",
+      Auth::Basic { .. } => "basic",
+      Auth::Bearer { .. } => "bearer",
+      Auth::ApiKey { .. } => "api key",
+      Auth::OAuth2(_) => "oauth2",
+      Auth::AwsSigV4(_) => "aws sigv4",
+      Auth::Hmac(_) => "hmac",
+      Auth::Digest(_) => "digest",
+    }
+  }
+
+  /// Fetches whatever credentials have to be obtained before the request can be prepared, caching them in the env.
+  pub fn authorize(&self, context: &mut Context) -> Result<(), http::Error> {
+    match self {
+      Auth::OAuth2(oauth2) => oauth2.authorize(context),
+      _ => Ok(()),
+    }
+  }
+
+  /// Adds the credentials to the request headers, or to the query string for query API keys.
+  pub fn apply(
+    &self,
+    headers: &mut HeaderMap,
+    query: &mut String,
+    env: &HashMap<String, String>,
+  ) -> Result<(), http::Error> {
+    let resolve = |value: &str| replace_env_variables(value, env);
+    match self {
+      Auth::None => {},
+      Auth::Basic { username, password } => {
+        let credentials = STANDARD.encode(format!("{}:{}", resolve(username), resolve(password)));
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Basic {}", credentials))?);
+      },
+      Auth::Bearer { token } => {
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", resolve(token)))?);
+      },
+      Auth::ApiKey { key, value, location: ApiKeyLocation::Header } => {
+        headers.insert(HeaderName::from_bytes(resolve(key).as_bytes())?, HeaderValue::from_str(&resolve(value))?);
+      },
+      Auth::ApiKey { key, value, location: ApiKeyLocation::Query } => {
+        let pair =
+          url::form_urlencoded::Serializer::new(String::new()).append_pair(&resolve(key), &resolve(value)).finish();
+        if !query.is_empty() {
+          query.push('&');
+        }
+        query.push_str(&pair);
+      },
+      Auth::OAuth2(oauth2) => {
+        let token = oauth2.cached_token(env).ok_or("No OAuth2 access token, authorize first")?;
+        headers.insert(AUTHORIZATION, HeaderValue::from_str(&format!("Bearer {}", token))?);
+      },
+      Auth::AwsSigV4(_) | Auth::Hmac(_) | Auth::Digest(_) => {},
+    }
+    Ok(())
+  }
+
+  /// The headers and query parameters the credentials go in, other than `Authorization`.
+  pub fn secrets(&self, env: &HashMap<String, String>) -> Secrets {
+    let resolve = |value: &str| replace_env_variables(value, env);
+    match self {
+      Auth::ApiKey { key, location: ApiKeyLocation::Header, .. } => {
+        Secrets { headers: vec![resolve(key).to_lowercase()], ..Default::default() }
+      },
+      Auth::ApiKey { key, location: ApiKeyLocation::Query, .. } => {
+        Secrets { query: vec![resolve(key)], ..Default::default() }
+      },
+      Auth::Hmac(hmac) => Secrets { headers: vec![resolve(&hmac.header).to_lowercase()], ..Default::default() },
+      _ => Secrets::default(),
+    }
+  }
+
+  /// The auth with its passwords, tokens and keys replaced by [`REDACTED`], unless they only refer to env variables.
+  pub fn redacted(&self) -> Auth {
+    let redact = |value: &mut String| {
+      if !PLACEHOLDER.replace_all(value, "").trim().is_empty() {
+        *value = String::from(REDACTED);
+      }
+    };
+    let mut auth = self.clone();
+    match &mut auth {
+      Auth::None => {},
+      Auth::Basic { password, .. } => redact(password),
+      Auth::Bearer { token } => redact(token),
+      Auth::ApiKey { value, .. } => redact(value),
+      Auth::OAuth2(oauth2) => {
+        redact(&mut oauth2.client_secret);
+        redact(&mut oauth2.password);
+        redact(&mut oauth2.refresh_token);
+      },
+      Auth::AwsSigV4(sigv4) => {
+        redact(&mut sigv4.secret_key);
+        redact(&mut sigv4.session_token);
+      },
+      Auth::Hmac(hmac) => redact(&mut hmac.secret),
+      Auth::Digest(digest) => redact(&mut digest.password),
+    }
+    auth
+  }
+
+  /// Signs the resolved request, for the auth types whose credentials depend on the final url, headers and body.
+  pub fn sign(&self, request: &mut PreparedRequest, env: &HashMap<String, String>) -> Result<(), http::Error> {
+    match self {
+      Auth::AwsSigV4(sigv4) => sigv4.sign(request, env, Utc::now()),
+      Auth::Hmac(hmac) => hmac.sign(request, env, Utc::now(), &hex::encode(rand::random::<[u8; 16]>())),
+      Auth::Digest(digest) => digest.sign(request, env),
+      _ => Ok(()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn env() -> HashMap<String, String> {
+    HashMap::from([(String::from("user"), String::from("goku")), (String::from("jwt"), String::from("abc"))])
+  }
+
+  #[test]
+  fn test_basic_auth_is_base64_encoded() {
+    let auth = Auth::Basic { username: String::from("{{user}}"), password: String::from("123456") };
+    let (mut headers, mut query) = (HeaderMap::new(), String::new());
+    auth.apply(&mut headers, &mut query, &env()).unwrap();
+    assert_eq!(headers.get(AUTHORIZATION).unwrap(), "Basic Z29rdToxMjM0NTY=");
+  }
+
+  #[test]
+  fn test_bearer_auth() {
+    let auth = Auth::Bearer { token: String::from("{{jwt}}") };
+    let (mut headers, mut query) = (HeaderMap::new(), String::new());
+    auth.apply(&mut headers, &mut query, &env()).unwrap();
+    assert_eq!(headers.get(AUTHORIZATION).unwrap(), "Bearer abc");
+  }
+
+  #[test]
+  fn test_api_key_in_query() {
+    let auth = Auth::ApiKey {
+      key: String::from("api_key"),
+      value: String::from("a b&{{jwt}}"),
+      location: ApiKeyLocation::Query,
+    };
+    let (mut headers, mut query) = (HeaderMap::new(), String::from("id=1"));
+    auth.apply(&mut headers, &mut query, &env()).unwrap();
+    assert_eq!(query, "id=1&api_key=a+b%26abc");
+    assert!(headers.is_empty());
+  }
+
+  #[test]
+  fn test_auth_deserialization() {
+    let auth: Auth = serde_json::from_str(r#"{ "type": "api_key", "key": "X-Api-Key", "value": "{{key}}" }"#).unwrap();
+    assert_eq!(auth, Auth::ApiKey {
+      key: String::from("X-Api-Key"),
+      value: String::from("{{key}}"),
+      location: ApiKeyLocation::Header
+    });
+  }
+}
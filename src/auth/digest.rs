@@ -0,0 +1,270 @@
+use std::{collections::HashMap, sync::Mutex};
+
+use lazy_static::lazy_static;
+use reqwest::header::{HeaderValue, AUTHORIZATION, WWW_AUTHENTICATE};
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+
+use crate::http::{self, replace_env_variables, PreparedRequest, ReqResponse};
+
+lazy_static! {
+  /// Last challenge seen per origin with the nonce count used so far, so later requests authenticate up front.
+  static ref CHALLENGES: Mutex<HashMap<String, (Challenge, u32)>> = Mutex::new(HashMap::new());
+}
+
+/// RFC 7616 HTTP Digest authentication.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct DigestAuth {
+  pub username: String,
+  pub password: String,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DigestAlgorithm {
+  Md5,
+  Md5Sess,
+  Sha256,
+  Sha256Sess,
+}
+
+impl DigestAlgorithm {
+  fn parse(name: &str) -> Option<Self> {
+    match name.to_uppercase().as_str() {
+      "MD5" => Some(DigestAlgorithm::Md5),
+      "MD5-SESS" => Some(DigestAlgorithm::Md5Sess),
+      "SHA-256" => Some(DigestAlgorithm::Sha256),
+      "SHA-256-SESS" => Some(DigestAlgorithm::Sha256Sess),
+      _ => None,
+    }
+  }
+
+  fn name(&self) -> &'static str {
+    match self {
+      DigestAlgorithm::Md5 => "MD5",
+      DigestAlgorithm::Md5Sess => "MD5-sess",
+      DigestAlgorithm::Sha256 => "SHA-256",
+      DigestAlgorithm::Sha256Sess => "SHA-256-sess",
+    }
+  }
+
+  fn hash(&self, data: &str) -> String {
+    match self {
+      DigestAlgorithm::Md5 | DigestAlgorithm::Md5Sess => hex::encode(md5::Md5::digest(data.as_bytes())),
+      DigestAlgorithm::Sha256 | DigestAlgorithm::Sha256Sess => hex::encode(Sha256::digest(data.as_bytes())),
+    }
+  }
+}
+
+/// A parsed `WWW-Authenticate: Digest ...` challenge.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Challenge {
+  pub realm: String,
+  pub nonce: String,
+  pub opaque: Option<String>,
+  pub algorithm: DigestAlgorithm,
+  /// Whether the server offered `qop=auth`; without it the RFC 2069 response is used
+  pub qop_auth: bool,
+}
+
+impl Challenge {
+  /// Parses the first supported Digest challenge of a `WWW-Authenticate` value.
+  pub fn parse(header: &str) -> Option<Self> {
+    let params = header.trim().strip_prefix("Digest")?;
+    let params = parse_params(params);
+    let algorithm = match params.get("algorithm") {
+      Some(name) => DigestAlgorithm::parse(name)?,
+      None => DigestAlgorithm::Md5,
+    };
+    Some(Challenge {
+      realm: params.get("realm").cloned().unwrap_or_default(),
+      nonce: params.get("nonce")?.clone(),
+      opaque: params.get("opaque").cloned(),
+      algorithm,
+      qop_auth: params.get("qop").map(|qop| qop.split(',').any(|q| q.trim() == "auth")).unwrap_or(false),
+    })
+  }
+}
+
+/// Splits `key=value, key="quoted, value"` pairs, lowercasing the keys.
+fn parse_params(input: &str) -> HashMap<String, String> {
+  let mut params = HashMap::new();
+  let mut chars = input.chars().peekable();
+  loop {
+    while chars.peek().map(|c| *c == ',' || c.is_whitespace()).unwrap_or(false) {
+      chars.next();
+    }
+    let key: String = chars.by_ref().take_while(|c| *c != '=').collect();
+    if key.is_empty() {
+      return params;
+    }
+    let mut value = String::new();
+    if chars.peek() == Some(&'"') {
+      chars.next();
+      while let Some(c) = chars.next() {
+        match c {
+          '\\' => value.extend(chars.next()),
+          '"' => break,
+          _ => value.push(c),
+        }
+      }
+    } else {
+      value = chars.by_ref().take_while(|c| *c != ',').collect::<String>().trim().to_string();
+    }
+    params.insert(key.trim().to_lowercase(), value);
+  }
+}
+
+fn origin(url: &url::Url) -> String {
+  url.origin().ascii_serialization()
+}
+
+impl DigestAuth {
+  /// Builds the `Authorization` header value answering `challenge` for the given request line.
+  pub fn authorization(
+    &self,
+    challenge: &Challenge,
+    method: &str,
+    uri: &str,
+    nonce_count: u32,
+    cnonce: &str,
+    env: &HashMap<String, String>,
+  ) -> String {
+    let username = replace_env_variables(&self.username, env);
+    let password = replace_env_variables(&self.password, env);
+    let algorithm = challenge.algorithm;
+    let nc = format!("{:08x}", nonce_count);
+
+    let mut ha1 = algorithm.hash(&format!("{}:{}:{}", username, challenge.realm, password));
+    if matches!(algorithm, DigestAlgorithm::Md5Sess | DigestAlgorithm::Sha256Sess) {
+      ha1 = algorithm.hash(&format!("{}:{}:{}", ha1, challenge.nonce, cnonce));
+    }
+    let ha2 = algorithm.hash(&format!("{}:{}", method, uri));
+    let response = if challenge.qop_auth {
+      algorithm.hash(&format!("{}:{}:{}:{}:auth:{}", ha1, challenge.nonce, nc, cnonce, ha2))
+    } else {
+      algorithm.hash(&format!("{}:{}:{}", ha1, challenge.nonce, ha2))
+    };
+
+    let mut header = format!(
+      "Digest username=\"{}\", realm=\"{}\", uri=\"{}\", algorithm={}, nonce=\"{}\"",
+      username,
+      challenge.realm,
+      uri,
+      algorithm.name(),
+      challenge.nonce
+    );
+    if challenge.qop_auth {
+      header.push_str(&format!(", nc={}, cnonce=\"{}\", qop=auth", nc, cnonce));
+    }
+    header.push_str(&format!(", response=\"{}\"", response));
+    if let Some(opaque) = &challenge.opaque {
+      header.push_str(&format!(", opaque=\"{}\"", opaque));
+    }
+    header
+  }
+
+  /// Authenticates up front with the last challenge seen for the request's origin, bumping its nonce count.
+  pub fn sign(&self, request: &mut PreparedRequest, env: &HashMap<String, String>) -> Result<(), http::Error> {
+    let url = url::Url::parse(&request.url)?;
+    let mut challenges = CHALLENGES.lock().unwrap();
+    if let Some((challenge, nonce_count)) = challenges.get_mut(&origin(&url)) {
+      *nonce_count += 1;
+      self.answer(request, &url, challenge, *nonce_count, env)?;
+    }
+    Ok(())
+  }
+
+  /// When `response` is a Digest challenge, returns `request` authenticated against it so it can be sent again.
+  pub fn respond(
+    &self,
+    request: &PreparedRequest,
+    response: &ReqResponse,
+    env: &HashMap<String, String>,
+  ) -> Result<Option<PreparedRequest>, http::Error> {
+    if response.status != Some(401) {
+      return Ok(None);
+    }
+    let challenge = match response
+      .header_map
+      .get_all(WWW_AUTHENTICATE)
+      .iter()
+      .filter_map(|value| value.to_str().ok().and_then(Challenge::parse))
+      .max_by_key(|challenge| matches!(challenge.algorithm, DigestAlgorithm::Sha256 | DigestAlgorithm::Sha256Sess))
+    {
+      Some(challenge) => challenge,
+      None => return Ok(None),
+    };
+
+    let url = url::Url::parse(&request.url)?;
+    let mut retry = request.clone();
+    self.answer(&mut retry, &url, &challenge, 1, env)?;
+    CHALLENGES.lock().unwrap().insert(origin(&url), (challenge, 1));
+    Ok(Some(retry))
+  }
+
+  fn answer(
+    &self,
+    request: &mut PreparedRequest,
+    url: &url::Url,
+    challenge: &Challenge,
+    nonce_count: u32,
+    env: &HashMap<String, String>,
+  ) -> Result<(), http::Error> {
+    let uri = match url.query().filter(|q| !q.is_empty()) {
+      Some(query) => format!("{}?{}", url.path(), query),
+      None => url.path().to_string(),
+    };
+    let cnonce = hex::encode(rand::random::<[u8; 16]>());
+    let authorization =
+      self.authorization(challenge, &request.method.to_string(), &uri, nonce_count, &cnonce, env);
+    request.headers.insert(AUTHORIZATION, HeaderValue::from_str(&authorization)?);
+    Ok(())
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  // Example from RFC 7616 section 3.9.1
+  const CHALLENGE: &str = r#"Digest realm="http-auth@example.org", qop="auth, auth-int", algorithm=SHA-256,
+    nonce="7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v", opaque="FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS""#;
+  const CNONCE: &str = "f2/wE4q74E6zIJEtWaHKaf5wv/H5QzzpXusqGemxURZJ";
+
+  fn auth() -> DigestAuth {
+    DigestAuth { username: String::from("Mufasa"), password: String::from("{{password}}") }
+  }
+
+  fn env() -> HashMap<String, String> {
+    HashMap::from([(String::from("password"), String::from("Circle of Life"))])
+  }
+
+  #[test]
+  fn test_parse_challenge() {
+    let challenge = Challenge::parse(CHALLENGE).unwrap();
+    assert_eq!(challenge.realm, "http-auth@example.org");
+    assert_eq!(challenge.nonce, "7ypf/xlj9XXwfDPEoM4URrv/xwf94BcCAzFZH4GiTo0v");
+    assert_eq!(challenge.opaque.as_deref(), Some("FQhe/qaU925kfnzjCev0ciny7QMkPqMAFRtzCUYo5tdS"));
+    assert_eq!(challenge.algorithm, DigestAlgorithm::Sha256);
+    assert!(challenge.qop_auth);
+    assert_eq!(Challenge::parse("Basic realm=\"x\""), None);
+  }
+
+  #[test]
+  fn test_rfc_7616_sha256_response() {
+    let challenge = Challenge::parse(CHALLENGE).unwrap();
+    let header = auth().authorization(&challenge, "GET", "/dir/index.html", 1, CNONCE, &env());
+    assert!(header.contains("nc=00000001"));
+    assert!(header.contains("response=\"753927fa0e85d155564e2e272a28d1802ca10daf4496794697cf8db5856cb6c1\""));
+  }
+
+  #[test]
+  fn test_rfc_7616_md5_response() {
+    let challenge = Challenge { algorithm: DigestAlgorithm::Md5, ..Challenge::parse(CHALLENGE).unwrap() };
+    let header = auth().authorization(&challenge, "GET", "/dir/index.html", 1, CNONCE, &env());
+    assert!(header.contains("algorithm=MD5"));
+    assert!(header.contains("response=\"8ca523f5e9506fed4657c9700eebdbec\""));
+  }
+}
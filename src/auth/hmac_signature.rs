@@ -0,0 +1,174 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use chrono::{DateTime, Utc};
+use hmac::{Hmac, Mac};
+use reqwest::header::{HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use sha1::Sha1;
+use sha2::{Digest, Sha256, Sha512};
+
+use crate::http::{self, parse_headers, replace_env_variables, PreparedRequest};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HmacAlgorithm {
+  Sha1,
+  #[default]
+  Sha256,
+  Sha512,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum SignatureEncoding {
+  #[default]
+  Hex,
+  Base64,
+}
+
+/// Signs a canonical string built from a template. Besides the env, the templates can use `{{method}}`, `{{path}}`,
+/// `{{query}}`, `{{body}}`, `{{body_sha256}}`, `{{timestamp}}` (unix seconds), `{{timestamp_iso}}` and `{{nonce}}`,
+/// and the header templates can also use `{{signature}}`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct HmacSignature {
+  pub secret: String,
+  #[serde(default)]
+  pub algorithm: HmacAlgorithm,
+  #[serde(default)]
+  pub encoding: SignatureEncoding,
+  pub string_to_sign: String,
+  #[serde(default = "default_header")]
+  pub header: String,
+  #[serde(default = "default_header_value")]
+  pub header_value: String,
+  /// Extra `Name: value` lines sent along with the signature, e.g. `X-Timestamp: {{timestamp}}`
+  #[serde(default)]
+  pub headers: String,
+}
+
+fn default_header() -> String {
+  String::from("X-Signature")
+}
+
+fn default_header_value() -> String {
+  String::from("{{signature}}")
+}
+
+impl HmacSignature {
+  pub fn sign(
+    &self,
+    request: &mut PreparedRequest,
+    env: &HashMap<String, String>,
+    now: DateTime<Utc>,
+    nonce: &str,
+  ) -> Result<(), http::Error> {
+    let url = url::Url::parse(&request.url)?;
+    let mut variables = env.clone();
+    variables.extend([
+      (String::from("method"), request.method.to_string()),
+      (String::from("path"), url.path().to_string()),
+      (String::from("query"), url.query().unwrap_or_default().to_string()),
+      (String::from("body"), request.sent_body().to_string()),
+      (String::from("body_sha256"), hex::encode(Sha256::digest(request.sent_body().as_bytes()))),
+      (String::from("timestamp"), now.timestamp().to_string()),
+      (String::from("timestamp_iso"), now.to_rfc3339()),
+      (String::from("nonce"), nonce.to_string()),
+    ]);
+
+    let string_to_sign = replace_env_variables(&self.string_to_sign, &variables);
+    let secret = replace_env_variables(&self.secret, env);
+    let digest = self.digest(secret.as_bytes(), string_to_sign.as_bytes());
+    let signature = match self.encoding {
+      SignatureEncoding::Hex => hex::encode(digest),
+      SignatureEncoding::Base64 => STANDARD.encode(digest),
+    };
+    variables.insert(String::from("signature"), signature);
+
+    request.headers.extend(parse_headers(&replace_env_variables(&self.headers, &variables))?);
+    request.headers.insert(
+      HeaderName::from_bytes(replace_env_variables(&self.header, &variables).as_bytes())?,
+      HeaderValue::from_str(&replace_env_variables(&self.header_value, &variables))?,
+    );
+    Ok(())
+  }
+
+  fn digest(&self, key: &[u8], data: &[u8]) -> Vec<u8> {
+    match self.algorithm {
+      HmacAlgorithm::Sha1 => mac::<Hmac<Sha1>>(key, data),
+      HmacAlgorithm::Sha256 => mac::<Hmac<Sha256>>(key, data),
+      HmacAlgorithm::Sha512 => mac::<Hmac<Sha512>>(key, data),
+    }
+  }
+}
+
+/// The HMAC of `data` under `key`, with the MAC given by `M`.
+pub(crate) fn mac<M: Mac + hmac::digest::KeyInit>(key: &[u8], data: &[u8]) -> Vec<u8> {
+  let mut mac = <M as Mac>::new_from_slice(key).expect("HMAC accepts keys of any length");
+  mac.update(data);
+  mac.finalize().into_bytes().to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+  use pretty_assertions::assert_eq;
+  use reqwest::header::HeaderMap;
+
+  use super::*;
+  use crate::components::home::server::HttpMethod;
+
+  fn signed(auth: &HmacSignature) -> HeaderMap {
+    let env = HashMap::from([(String::from("secret"), String::from("s3cret"))]);
+    let mut request = PreparedRequest {
+      method: HttpMethod::POST,
+      url: String::from("https://pay.example.com/payments?"),
+      headers: HeaderMap::new(),
+      body: String::from(r#"{"amount":10}"#),
+      ..Default::default()
+    };
+    auth.sign(&mut request, &env, Utc.timestamp_opt(1_700_000_000, 0).unwrap(), "n0nce").unwrap();
+    request.headers
+  }
+
+  fn auth(algorithm: HmacAlgorithm, encoding: SignatureEncoding) -> HmacSignature {
+    HmacSignature {
+      secret: String::from("{{secret}}"),
+      algorithm,
+      encoding,
+      string_to_sign: String::from("{{method}}\n{{path}}\n{{timestamp}}\n{{body_sha256}}"),
+      header: default_header(),
+      header_value: default_header_value(),
+      headers: String::from("X-Timestamp: {{timestamp}}\nX-Nonce: {{nonce}}"),
+    }
+  }
+
+  #[test]
+  fn test_sha256_hex_signature_and_extra_headers() {
+    let headers = signed(&auth(HmacAlgorithm::Sha256, SignatureEncoding::Hex));
+    assert_eq!(headers.get("x-signature").unwrap(), "3cd45d2d3c0bb46ab3c769a5b0bae98a229f127e93558fd23ce4f0a8d969e643");
+    assert_eq!(headers.get("x-timestamp").unwrap(), "1700000000");
+    assert_eq!(headers.get("x-nonce").unwrap(), "n0nce");
+  }
+
+  #[test]
+  fn test_sha512_base64_signature_in_custom_header() {
+    let auth = HmacSignature {
+      header: String::from("Authorization"),
+      header_value: String::from("HMAC {{signature}}"),
+      ..auth(HmacAlgorithm::Sha512, SignatureEncoding::Base64)
+    };
+    let headers = signed(&auth);
+    assert_eq!(
+      headers.get("authorization").unwrap(),
+      "HMAC ekp5I/OQvc8mrwKLc7GJfVsPdnWGgG7Ys1iYHOPtcbVE6poXJACxOd8hEngoXC4yjg0OGAmp5N01Az+C3OsJIw=="
+    );
+  }
+
+  #[test]
+  fn test_sha1_matches_rfc_2202() {
+    let auth = auth(HmacAlgorithm::Sha1, SignatureEncoding::Hex);
+    let digest = auth.digest(b"Jefe", b"what do ya want for nothing?");
+    assert_eq!(hex::encode(digest), "effcdf6ae5eb2fa2d27416d5f184df9c259a7c79");
+  }
+}
@@ -0,0 +1,147 @@
+use std::{
+  io::{BufRead, BufReader, ErrorKind, Write},
+  net::TcpListener,
+  process::{Command, Stdio},
+  sync::mpsc,
+  thread,
+  time::{Duration, Instant},
+};
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use sha2::{Digest, Sha256};
+use tracing::{error, info};
+
+use crate::http;
+
+/// How long a connection to the redirect listener may stay silent before the next one is accepted.
+const IDLE_CONNECTION_TIMEOUT: Duration = Duration::from_secs(5);
+
+const CALLBACK_PAGE: &str =
+  "<html><body>cartero received the authorization code, you can close this window.</body></html>";
+
+/// A random URL-safe string, long enough to be used as a PKCE code verifier or as the `state` parameter.
+pub fn random_token() -> String {
+  let bytes: [u8; 32] = rand::random();
+  URL_SAFE_NO_PAD.encode(bytes)
+}
+
+/// The S256 code challenge for `verifier`, as defined by RFC 7636.
+pub fn challenge(verifier: &str) -> String {
+  URL_SAFE_NO_PAD.encode(Sha256::digest(verifier.as_bytes()))
+}
+
+/// Opens `url` in the default browser. It is also logged and sent to `notices`, to be shown while the flow waits, so
+/// it can be copied by hand when no browser is available.
+pub fn open_in_browser(url: &str, notices: Option<&mpsc::Sender<String>>) {
+  info!("Authorize cartero at: {}", url);
+  if let Some(notices) = notices {
+    let _ = notices.send(format!("Authorize cartero in the browser: {}", url));
+  }
+  let opener = if cfg!(target_os = "macos") { "open" } else { "xdg-open" };
+  let result = Command::new(opener).arg(url).stdout(Stdio::null()).stderr(Stdio::null()).spawn();
+  if let Err(e) = result {
+    error!("Could not open a browser, visit the URL above: {:?}", e);
+  }
+}
+
+/// Waits on `listener` for the authorization server to redirect the browser back, and returns the `code` once the
+/// `state` matches. Connections that send nothing, like browser preconnects, are given up on after a while.
+pub fn wait_for_code(listener: &TcpListener, state: &str, timeout: Duration) -> Result<String, http::Error> {
+  listener.set_nonblocking(true)?;
+  let deadline = Instant::now() + timeout;
+
+  loop {
+    let mut stream = match listener.accept() {
+      Ok((stream, _)) => stream,
+      Err(e) if e.kind() == ErrorKind::WouldBlock => {
+        if Instant::now() > deadline {
+          return Err("Timed out waiting for the OAuth2 redirect".into());
+        }
+        thread::sleep(Duration::from_millis(100));
+        continue;
+      },
+      Err(e) => return Err(e.into()),
+    };
+    stream.set_nonblocking(false)?;
+    let left = deadline.saturating_duration_since(Instant::now());
+    stream.set_read_timeout(Some(left.clamp(Duration::from_millis(1), IDLE_CONNECTION_TIMEOUT)))?;
+
+    let mut request_line = String::new();
+    match BufReader::new(&stream).read_line(&mut request_line) {
+      Ok(_) => {},
+      Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => continue,
+      Err(e) => return Err(e.into()),
+    }
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let url = url::Url::parse(&format!("http://localhost{}", target))?;
+    let param = |name: &str| url.query_pairs().find(|(key, _)| key == name).map(|(_, value)| value.to_string());
+
+    let page = format!(
+      "HTTP/1.1 200 OK\r\nContent-Type: text/html\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+      CALLBACK_PAGE.len(),
+      CALLBACK_PAGE
+    );
+    stream.write_all(page.as_bytes())?;
+
+    if let Some(error) = param("error") {
+      return Err(format!("Authorization failed: {}", error).into());
+    }
+    match (param("code"), param("state")) {
+      (Some(code), Some(received)) if received == state => return Ok(code),
+      (Some(_), _) => return Err("OAuth2 redirect carried an unexpected state".into()),
+      // favicon and other requests the browser makes on its own
+      _ => continue,
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::net::TcpStream;
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_challenge_matches_rfc_7636_example() {
+    assert_eq!(challenge("dBjftJeZ4CVP-mB92K27uhbUJU1p1r_wW1gFWFOEjXk"), "E9Melhoa2OwvFrEMTJguCHaoeK1t8URWbuGJSstw-cM");
+  }
+
+  #[test]
+  fn test_random_token_is_a_valid_verifier() {
+    let token = random_token();
+    assert_eq!(token.len(), 43);
+    assert!(token.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_'));
+  }
+
+  #[test]
+  fn test_wait_for_code_captures_the_redirect() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let browser = thread::spawn(move || {
+      for target in ["/favicon.ico", "/callback?code=abc&state=xyz"] {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        write!(stream, "GET {} HTTP/1.1\r\nHost: localhost\r\n\r\n", target).unwrap();
+        let mut status = String::new();
+        BufReader::new(stream).read_line(&mut status).unwrap();
+      }
+    });
+
+    let code = wait_for_code(&listener, "xyz", Duration::from_secs(5)).unwrap();
+    browser.join().unwrap();
+    assert_eq!(code, "abc");
+  }
+
+  #[test]
+  fn test_silent_connection_does_not_outlive_the_deadline() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let preconnect = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+
+    let started = Instant::now();
+    let result = wait_for_code(&listener, "xyz", Duration::from_millis(300));
+    assert!(result.unwrap_err().to_string().starts_with("Timed out"));
+    assert!(started.elapsed() < Duration::from_secs(2));
+    drop(preconnect);
+  }
+}
@@ -1,6 +1,6 @@
 use std::path::PathBuf;
 
-use clap::Parser;
+use clap::{Parser, Subcommand};
 
 use crate::utils::version;
 
@@ -18,4 +18,16 @@ pub struct Cli {
     default_value_t = 60.0
   )]
   pub frame_rate: f64,
+
+  #[command(subcommand)]
+  pub command: Option<Commands>,
+}
+
+#[derive(Subcommand, Debug)]
+pub enum Commands {
+  /// Send every saved request and diff the responses against their golden snapshots
+  Snapshot {
+    #[arg(long, help = "Record the current responses as the new snapshots instead of checking them")]
+    update_snapshots: bool,
+  },
 }
@@ -0,0 +1,249 @@
+use std::sync::{Arc, Mutex};
+
+use chrono::{Local, Utc};
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use log::error;
+use ratatui::{prelude::*, widgets::*};
+
+use super::{centered_rect, subcomponent::Subcomponent, Frame};
+use crate::repository::cookies::{Cookie, CookieJar, CookieRepository};
+
+/// What the input line is being used for.
+#[derive(Clone, PartialEq)]
+enum Editing {
+  Add,
+  /// Replaces the cookie at this index
+  Edit(usize),
+}
+
+/// Lists the cookie jar of the active environment by domain and lets cookies be added, edited and deleted.
+#[derive(Default)]
+pub struct Cookies {
+  cookie_repository: Arc<Mutex<CookieRepository>>,
+  environment: String,
+  jar: CookieJar,
+  table_state: TableState,
+  editing: Option<Editing>,
+  input: String,
+}
+
+impl Cookies {
+  pub fn new(cookie_repository: Arc<Mutex<CookieRepository>>) -> Self {
+    Cookies { cookie_repository, ..Default::default() }
+  }
+
+  /// Loads the jar of `environment`, the server cookies are scoped to.
+  pub fn load(&mut self, environment: String) {
+    self.jar = self.cookie_repository.lock().unwrap().get_jar(&environment);
+    self.environment = environment;
+    self.editing = None;
+    self.table_state.select(if self.jar.cookies().is_empty() { None } else { Some(0) });
+  }
+
+  pub fn is_editing(&self) -> bool {
+    self.editing.is_some()
+  }
+
+  /// Cookies sorted by domain, then path and name, the order they are listed in.
+  fn sorted(&self) -> Vec<Cookie> {
+    let mut cookies = self.jar.cookies().to_vec();
+    cookies.sort_by(|a, b| (&a.domain, &a.path, &a.name).cmp(&(&b.domain, &b.path, &b.name)));
+    cookies
+  }
+
+  fn get_selected(&self) -> Option<Cookie> {
+    self.table_state.selected().and_then(|idx| self.sorted().get(idx).cloned())
+  }
+
+  fn save(&mut self) {
+    let result = self.cookie_repository.lock().unwrap().set_jar(&self.environment, self.jar.clone());
+    if let Err(e) = result {
+      error!("Could not write cookies: {:?}", e);
+    }
+    let len = self.jar.cookies().len();
+    let selected = self.table_state.selected().unwrap_or(0);
+    self.table_state.select(if len == 0 { None } else { Some(selected.min(len - 1)) });
+  }
+
+  fn start_editing(&mut self, editing: Editing) {
+    self.input = match &editing {
+      Editing::Add => String::new(),
+      Editing::Edit(idx) => self.sorted().get(*idx).map(|c| c.to_set_cookie()).unwrap_or_default(),
+    };
+    self.editing = Some(editing);
+  }
+
+  /// Parses the input line as a `Set-Cookie` value and stores it.
+  fn commit(&mut self) {
+    let editing = match self.editing.take() {
+      Some(editing) => editing,
+      None => return,
+    };
+    let (url, replaced) = match &editing {
+      Editing::Add => (self.environment.clone(), None),
+      Editing::Edit(idx) => match self.sorted().get(*idx) {
+        Some(cookie) => (format!("https://{}{}", cookie.domain, cookie.path), Some(cookie.clone())),
+        None => return,
+      },
+    };
+    let cookie = url::Url::parse(&url).ok().and_then(|url| Cookie::parse(&self.input, &url, Utc::now()));
+    match cookie {
+      Some(cookie) => {
+        if let Some(old) = replaced {
+          self.jar.remove(&old.domain, &old.path, &old.name);
+        }
+        self.jar.insert(cookie, Utc::now());
+        self.save();
+      },
+      None => error!("Not a valid cookie for {}: {}", url, self.input),
+    }
+  }
+
+  fn delete_selected(&mut self) {
+    if let Some(cookie) = self.get_selected() {
+      self.jar.remove(&cookie.domain, &cookie.path, &cookie.name);
+      self.save();
+    }
+  }
+
+  fn clear_selected_domain(&mut self) {
+    if let Some(cookie) = self.get_selected() {
+      self.jar.clear_domain(&cookie.domain);
+      self.save();
+    }
+  }
+
+  pub fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    let popup = centered_rect(90, 60, rect);
+    f.render_widget(Clear, popup);
+    let chunks = Layout::default()
+      .direction(Direction::Vertical)
+      .constraints([Constraint::Min(3), Constraint::Length(3)].as_ref())
+      .split(popup);
+
+    let now = Utc::now();
+    let flag = |set: bool| if set { "yes" } else { "" };
+    let rows: Vec<Row> = self
+      .sorted()
+      .iter()
+      .map(|cookie| {
+        let expires = match cookie.expires {
+          Some(expires) => expires.with_timezone(&Local).format("%Y-%m-%d %H:%M").to_string(),
+          None => String::from("session"),
+        };
+        let style = if cookie.is_expired(now) { Style::default().fg(Color::DarkGray) } else { Style::default() };
+        let domain = if cookie.host_only { cookie.domain.clone() } else { format!(".{}", cookie.domain) };
+        Row::new(vec![
+          domain,
+          cookie.name.clone(),
+          cookie.value.clone(),
+          cookie.path.clone(),
+          expires,
+          flag(cookie.secure).to_string(),
+          flag(cookie.http_only).to_string(),
+        ])
+        .style(style)
+      })
+      .collect();
+
+    let widths = [
+      Constraint::Percentage(18),
+      Constraint::Percentage(14),
+      Constraint::Percentage(30),
+      Constraint::Percentage(10),
+      Constraint::Percentage(14),
+      Constraint::Percentage(6),
+      Constraint::Percentage(8),
+    ];
+    let table = Table::new(rows)
+      .header(
+        Row::new(vec!["Domain", "Name", "Value", "Path", "Expires", "Secure", "HttpOnly"])
+          .style(Style::default().add_modifier(Modifier::BOLD)),
+      )
+      .widths(&widths)
+      .block(
+        Block::default()
+          .borders(Borders::ALL)
+          .style(self.get_style(!self.is_editing()))
+          .title(format!("Cookies for {} (a: add, e: edit, d: delete, D: clear domain, esc: close)", self.environment))
+          .border_type(BorderType::Plain),
+      )
+      .highlight_style(Style::default().bg(Color::Yellow).add_modifier(Modifier::BOLD));
+    f.render_stateful_widget(table, chunks[0], &mut self.table_state);
+
+    let title = match self.editing {
+      Some(Editing::Add) => "New cookie, as a Set-Cookie value (enter: save, esc: cancel)",
+      Some(Editing::Edit(_)) => "Edit cookie (enter: save, esc: cancel)",
+      None => "Set-Cookie",
+    };
+    let input = Paragraph::new(self.input.as_str()).block(
+      Block::default()
+        .borders(Borders::ALL)
+        .style(self.get_style(self.is_editing()))
+        .title(title)
+        .border_type(BorderType::Plain),
+    );
+    f.render_widget(input, chunks[1]);
+    if self.is_editing() {
+      self.set_cursor(f, chunks[1], &self.input);
+    }
+    Ok(())
+  }
+
+  fn next(&mut self) {
+    if let Some(selected) = self.table_state.selected() {
+      self.table_state.select(Some((selected + 1).min(self.jar.cookies().len().saturating_sub(1))));
+    }
+  }
+
+  fn previous(&mut self) {
+    if let Some(selected) = self.table_state.selected() {
+      self.table_state.select(Some(selected.saturating_sub(1)));
+    }
+  }
+}
+
+impl Subcomponent for Cookies {
+  fn handle_normal_key_events(&mut self, key: KeyEvent) {
+    self.handle_key_events(key);
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) {
+    if self.is_editing() {
+      match key.code {
+        KeyCode::Enter => self.commit(),
+        KeyCode::Esc => self.editing = None,
+        KeyCode::Char(c) => self.push(c),
+        KeyCode::Backspace => self.pop(),
+        _ => {},
+      }
+      return;
+    }
+    match key {
+      KeyEvent { modifiers: _, code: KeyCode::Char('j') | KeyCode::Down, kind: _, state: _ } => self.next(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('k') | KeyCode::Up, kind: _, state: _ } => self.previous(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('a'), kind: _, state: _ } => self.start_editing(Editing::Add),
+      KeyEvent { modifiers: _, code: KeyCode::Char('e'), kind: _, state: _ } => {
+        if let Some(idx) = self.table_state.selected() {
+          self.start_editing(Editing::Edit(idx));
+        }
+      },
+      KeyEvent { modifiers: _, code: KeyCode::Char('d'), kind: _, state: _ } => self.delete_selected(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('D'), kind: _, state: _ } => self.clear_selected_domain(),
+      _ => {},
+    }
+  }
+
+  fn push(&mut self, c: char) {
+    self.input.push(c);
+  }
+
+  fn pop(&mut self) {
+    self.input.pop();
+  }
+
+  fn clear(&mut self) {
+    self.input.clear();
+  }
+}
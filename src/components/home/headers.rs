@@ -16,12 +16,16 @@ impl Headers {
 
   pub fn draw(&self, f: &mut Frame<'_>, rect: Rect, is_focused: bool) -> Result<()> {
     let repo = self.repository.lock().unwrap();
+    let title = match repo.get_effective_auth() {
+      Some(auth) => format!("Headers (auth: {})", auth.name()),
+      None => String::from("Headers"),
+    };
     let headers =
       Paragraph::new(repo.get_headers()).style(Style::default().fg(Color::LightCyan)).alignment(Alignment::Left).block(
         Block::default()
           .borders(Borders::ALL)
           .style(self.get_style(is_focused))
-          .title("Headers")
+          .title(title)
           .border_type(BorderType::Plain),
       );
 
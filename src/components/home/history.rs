@@ -0,0 +1,122 @@
+use std::sync::{Arc, Mutex};
+
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::{centered_rect, subcomponent::Subcomponent, Frame};
+use crate::repository::{
+  history::{HistoryEntry, HistoryRepository},
+  local_storage::LocalStorageRepository,
+};
+
+#[derive(Default)]
+pub struct History {
+  repository: Arc<Mutex<LocalStorageRepository>>,
+  history: Arc<Mutex<HistoryRepository>>,
+  list_state: ListState,
+}
+
+impl History {
+  pub fn new(repository: Arc<Mutex<LocalStorageRepository>>, history: Arc<Mutex<HistoryRepository>>) -> Self {
+    History { repository, history, list_state: ListState::default() }
+  }
+
+  pub fn reset(&mut self) {
+    self.list_state.select(Some(0));
+  }
+
+  /// The entry under the cursor, out of the history of the active request.
+  pub fn get_selected(&self) -> Option<HistoryEntry> {
+    let request_id = self.repository.lock().unwrap().get_active_request().id;
+    let history = self.history.lock().unwrap();
+    let entries = history.entries_for(&request_id);
+    self.list_state.selected().and_then(|idx| entries.get(idx).map(|entry| (*entry).clone()))
+  }
+
+  pub fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    let request_id = self.repository.lock().unwrap().get_active_request().id;
+    let history = self.history.lock().unwrap();
+    let entries = history.entries_for(&request_id);
+
+    if entries.is_empty() {
+      self.list_state.select(None);
+    } else {
+      let selected = self.list_state.selected().unwrap_or(0);
+      self.list_state.select(Some(selected.min(entries.len() - 1)));
+    }
+
+    let items: Vec<ListItem> = entries
+      .iter()
+      .map(|entry| {
+        let status = entry.status.map(|s| s.to_string()).unwrap_or_else(|| String::from("ERR"));
+        ListItem::new(Line::from(vec![
+          Span::styled(entry.timestamp.format("%Y-%m-%d %H:%M:%S ").to_string(), Style::default().fg(Color::Gray)),
+          Span::styled(format!("{:<4}", status), status_style(entry.status)),
+          Span::styled(format!("{:>6}ms ", entry.elapsed_ms), Style::default().fg(Color::Gray)),
+          Span::styled(entry.request.method.to_string(), entry.request.method.get_style()),
+          Span::styled(format!(" {}", entry.url), Style::default().fg(Color::LightCyan)),
+        ]))
+      })
+      .collect();
+
+    let list = List::new(items)
+      .block(
+        Block::default()
+          .borders(Borders::ALL)
+          .style(self.get_style(true))
+          .title("History (enter: view, r: re-send, esc: close)")
+          .border_type(BorderType::Plain),
+      )
+      .highlight_style(Style::default().bg(Color::Yellow).add_modifier(Modifier::BOLD));
+
+    let popup = centered_rect(80, 60, rect);
+    f.render_widget(Clear, popup);
+    f.render_stateful_widget(list, popup, &mut self.list_state);
+
+    Ok(())
+  }
+
+  fn next(&mut self) {
+    let request_id = self.repository.lock().unwrap().get_active_request().id;
+    let len = self.history.lock().unwrap().entries_for(&request_id).len();
+    if let Some(selected) = self.list_state.selected() {
+      self.list_state.select(Some((selected + 1).min(len.saturating_sub(1))));
+    }
+  }
+
+  fn previous(&mut self) {
+    if let Some(selected) = self.list_state.selected() {
+      self.list_state.select(Some(selected.saturating_sub(1)));
+    }
+  }
+}
+
+pub(crate) fn status_style(status: Option<u16>) -> Style {
+  match status {
+    Some(200..=299) => Style::default().fg(Color::Green),
+    Some(300..=399) => Style::default().fg(Color::Yellow),
+    Some(_) => Style::default().fg(Color::Red),
+    None => Style::default().fg(Color::Red).add_modifier(Modifier::BOLD),
+  }
+}
+
+impl Subcomponent for History {
+  fn handle_normal_key_events(&mut self, key: KeyEvent) {
+    self.handle_key_events(key);
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) {
+    match key {
+      KeyEvent { modifiers: _, code: KeyCode::Char('j') | KeyCode::Down, kind: _, state: _ } => self.next(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('k') | KeyCode::Up, kind: _, state: _ } => self.previous(),
+      _ => {},
+    }
+  }
+
+  fn push(&mut self, c: char) {}
+
+  fn pop(&mut self) {}
+
+  fn clear(&mut self) {}
+}
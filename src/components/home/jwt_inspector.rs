@@ -0,0 +1,199 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+use serde_json::Value;
+
+use super::{centered_rect, subcomponent::Subcomponent, Frame};
+use crate::{
+  http::ReqResponse,
+  jwt::{self, Jwt},
+};
+
+/// Lists the JWTs found in the env and the last response, and decodes the selected one.
+#[derive(Default)]
+pub struct JwtInspector {
+  tokens: Vec<(String, String)>,
+  list_state: ListState,
+  secret: String,
+  editing_secret: bool,
+}
+
+impl JwtInspector {
+  /// Collects the tokens to inspect, labelled with where they were found.
+  pub fn load(&mut self, env: &HashMap<String, String>, response: Option<&ReqResponse>) {
+    let mut tokens: Vec<(String, String)> = env
+      .iter()
+      .filter(|(_, value)| Jwt::decode(value).is_some())
+      .map(|(key, value)| (format!("env: {}", key), value.clone()))
+      .collect();
+    tokens.sort();
+
+    if let Some(response) = response {
+      tokens.extend(jwt::find_in(&response.headers).into_iter().map(|token| (String::from("response header"), token)));
+      match serde_json::from_str::<Value>(&response.body) {
+        Ok(body) => collect_from_json(&body, String::new(), &mut tokens),
+        Err(_) => {
+          tokens.extend(jwt::find_in(&response.body).into_iter().map(|token| (String::from("response body"), token)))
+        },
+      }
+    }
+
+    self.tokens = tokens;
+    self.list_state.select(if self.tokens.is_empty() { None } else { Some(0) });
+    self.editing_secret = false;
+  }
+
+  pub fn is_editing_secret(&self) -> bool {
+    self.editing_secret
+  }
+
+  pub fn edit_secret(&mut self, editing: bool) {
+    self.editing_secret = editing;
+  }
+
+  fn get_selected(&self) -> Option<&(String, String)> {
+    self.list_state.selected().and_then(|idx| self.tokens.get(idx))
+  }
+
+  pub fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    let popup = centered_rect(80, 70, rect);
+    f.render_widget(Clear, popup);
+    let chunks = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Percentage(30), Constraint::Percentage(70)].as_ref())
+      .split(popup);
+
+    let items: Vec<ListItem> = self.tokens.iter().map(|(source, _)| ListItem::new(source.clone())).collect();
+    let list = List::new(items)
+      .block(
+        Block::default()
+          .borders(Borders::ALL)
+          .style(self.get_style(true))
+          .title("JWTs (s: secret, esc: close)")
+          .border_type(BorderType::Plain),
+      )
+      .highlight_style(Style::default().bg(Color::Yellow).add_modifier(Modifier::BOLD));
+    f.render_stateful_widget(list, chunks[0], &mut self.list_state);
+
+    let text = match self.get_selected().and_then(|(_, token)| Jwt::decode(token)) {
+      Some(jwt) => self.describe(&jwt),
+      None => Text::from("No JWT found in the env or the last response"),
+    };
+    let detail = Paragraph::new(text).wrap(Wrap { trim: false }).block(
+      Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default().fg(Color::White))
+        .title("Decoded")
+        .border_type(BorderType::Plain),
+    );
+    f.render_widget(detail, chunks[1]);
+    Ok(())
+  }
+
+  fn describe(&self, jwt: &Jwt) -> Text<'static> {
+    let now = Utc::now();
+    let mut lines = vec![Line::styled("Header", Style::default().add_modifier(Modifier::BOLD))];
+    lines.extend(pretty(&jwt.header));
+    lines.push(Line::styled("Claims", Style::default().add_modifier(Modifier::BOLD)));
+    lines.extend(pretty(&jwt.claims));
+    lines.push(Line::from(""));
+
+    for claim in ["exp", "iat", "nbf"] {
+      if let Some(timestamp) = jwt.timestamp(claim) {
+        let style = if claim == "exp" && jwt.is_expired(now) {
+          Style::default().fg(Color::Red)
+        } else {
+          Style::default().fg(Color::Green)
+        };
+        lines.push(Line::styled(format!("{}: {}", claim, jwt::describe_timestamp(claim, timestamp, now)), style));
+      }
+    }
+
+    let cursor = if self.editing_secret { "_" } else { "" };
+    let signature = match (jwt.verify_hmac(&self.secret), self.secret.is_empty()) {
+      (None, _) => Span::raw(format!("{} signature, only HMAC signatures can be verified", jwt.algorithm())),
+      (Some(_), true) => Span::raw(format!("{} signature, press s to enter the secret", jwt.algorithm())),
+      (Some(true), false) => Span::styled("Signature valid", Style::default().fg(Color::Green)),
+      (Some(false), false) => Span::styled("Signature invalid", Style::default().fg(Color::Red)),
+    };
+    lines.push(Line::from(""));
+    lines.push(signature.into());
+    lines.push(Line::from(format!("Secret: {}{}", "*".repeat(self.secret.chars().count()), cursor)));
+    Text::from(lines)
+  }
+
+  fn next(&mut self) {
+    if let Some(selected) = self.list_state.selected() {
+      self.list_state.select(Some((selected + 1).min(self.tokens.len().saturating_sub(1))));
+    }
+  }
+
+  fn previous(&mut self) {
+    if let Some(selected) = self.list_state.selected() {
+      self.list_state.select(Some(selected.saturating_sub(1)));
+    }
+  }
+}
+
+fn pretty(value: &Value) -> Vec<Line<'static>> {
+  serde_json::to_string_pretty(value).unwrap_or_default().lines().map(|l| Line::from(l.to_string())).collect()
+}
+
+/// Walks a JSON body, labelling every JWT string value with its JSON pointer.
+fn collect_from_json(value: &Value, pointer: String, tokens: &mut Vec<(String, String)>) {
+  match value {
+    Value::String(s) if Jwt::decode(s).is_some() => tokens.push((format!("body: {}", pointer), s.clone())),
+    Value::Object(map) => {
+      for (key, value) in map {
+        collect_from_json(value, format!("{}/{}", pointer, key), tokens);
+      }
+    },
+    Value::Array(values) => {
+      for (idx, value) in values.iter().enumerate() {
+        collect_from_json(value, format!("{}/{}", pointer, idx), tokens);
+      }
+    },
+    _ => {},
+  }
+}
+
+impl Subcomponent for JwtInspector {
+  fn handle_normal_key_events(&mut self, key: KeyEvent) {
+    self.handle_key_events(key);
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) {
+    if self.editing_secret {
+      match key.code {
+        KeyCode::Char(c) => self.push(c),
+        KeyCode::Backspace => self.pop(),
+        _ => {},
+      }
+      return;
+    }
+    match key {
+      KeyEvent { modifiers: _, code: KeyCode::Char('j') | KeyCode::Down, kind: _, state: _ } => self.next(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('k') | KeyCode::Up, kind: _, state: _ } => self.previous(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('s'), kind: _, state: _ } => {
+        self.clear();
+        self.editing_secret = true;
+      },
+      _ => {},
+    }
+  }
+
+  fn push(&mut self, c: char) {
+    self.secret.push(c);
+  }
+
+  fn pop(&mut self) {
+    self.secret.pop();
+  }
+
+  fn clear(&mut self) {
+    self.secret.clear();
+  }
+}
@@ -40,17 +40,31 @@ use tui_input::{backend::crossterm::EventHandler, Input};
 use super::{Component, Frame};
 use crate::{
   action::Action,
-  repository::local_storage::{self, LocalStorageRepository},
+  error::Error as AppError,
+  http::{self, ReqResponse},
+  jwt,
+  refresh::{self, Outcome},
+  repository::{
+    cookies::CookieRepository,
+    history::{HistoryEntry, HistoryRepository},
+    local_storage::{self, LocalStorageRepository, RequestInput},
+  },
+  snapshot,
 };
 
+mod cookies;
 mod headers;
+mod history;
+mod jwt_inspector;
 mod path;
 mod payload;
+mod preview;
 mod querystring;
 mod request_list;
 mod request_response;
 pub(crate) mod server;
 mod subcomponent;
+mod timeline;
 
 #[derive(Default, Copy, Clone, PartialEq, Eq)]
 pub enum Mode {
@@ -60,11 +74,8 @@ pub enum Mode {
   Processing,
 }
 
-#[derive(Default, Clone)]
-struct ReqResponse {
-  headers: String,
-  body: String,
-}
+/// What a background send hands back: the exchanges, and the context after parsing rules updated its env.
+type SendOutcome = (Result<Outcome, http::Error>, http::Context);
 
 #[derive(Default)]
 pub struct Home {
@@ -75,8 +86,10 @@ pub struct Home {
   pub mode: Mode,
   pub input: Input,
   pub action_tx: Option<UnboundedSender<Action>>,
-  pub tx: Option<mpsc::Sender<ReqResponse>>,
-  pub rx: Option<mpsc::Receiver<ReqResponse>>,
+  pub tx: Option<mpsc::Sender<SendOutcome>>,
+  pub rx: Option<mpsc::Receiver<SendOutcome>>,
+  /// What the request being sent has to tell, `None` when no request is running
+  notices: Option<mpsc::Receiver<String>>,
   pub keymap: HashMap<KeyEvent, Action>,
   pub text: Vec<String>,
   pub last_events: Vec<KeyEvent>,
@@ -97,11 +110,23 @@ pub struct Home {
   pub payload: payload::Payload,
   pub headers: headers::Headers,
   pub request_response: request_response::RequestResponse,
+  pub history: history::History,
+  pub history_repository: Arc<Mutex<HistoryRepository>>,
+  pub cookie_repository: Arc<Mutex<CookieRepository>>,
+  pub timeline: timeline::Timeline,
+  pub jwt_inspector: jwt_inspector::JwtInspector,
+  pub cookies: cookies::Cookies,
+  pub preview: preview::RequestPreview,
   pub active_widget: MenuItem,
+  last_widget: MenuItem,
 }
 
 impl Home {
-  pub fn new(repository: Arc<Mutex<LocalStorageRepository>>) -> Self {
+  pub fn new(
+    repository: Arc<Mutex<LocalStorageRepository>>,
+    history_repository: Arc<Mutex<HistoryRepository>>,
+    cookie_repository: Arc<Mutex<CookieRepository>>,
+  ) -> Self {
     let (tx, rx) = mpsc::channel();
     let server = server::Server::new(Arc::clone(&repository));
     let path = path::Path::new(Arc::clone(&repository));
@@ -109,6 +134,8 @@ impl Home {
     let payload = payload::Payload::new(Arc::clone(&repository));
     let headers = headers::Headers::new(Arc::clone(&repository));
     let request_list = request_list::RequestList::new(Arc::clone(&repository));
+    let history = history::History::new(Arc::clone(&repository), Arc::clone(&history_repository));
+    let cookies = cookies::Cookies::new(Arc::clone(&cookie_repository));
     Home {
       tx: Some(tx),
       rx: Some(rx),
@@ -119,6 +146,10 @@ impl Home {
       querystring,
       payload,
       headers,
+      history,
+      cookies,
+      history_repository,
+      cookie_repository,
       ..Default::default()
     }
   }
@@ -149,101 +180,253 @@ impl Home {
       MenuItem::Headers => &mut self.headers,
       MenuItem::ServerListPopup => &mut self.server,
       MenuItem::JsonPath => &mut self.request_response,
+      MenuItem::HistoryPopup => &mut self.history,
+      MenuItem::TimelinePopup => &mut self.timeline,
+      MenuItem::JwtPopup => &mut self.jwt_inspector,
+      MenuItem::CookiesPopup => &mut self.cookies,
+      MenuItem::PreviewPopup => &mut self.preview,
       _ => panic!("Not implemented"),
     }
   }
 
   fn process_request(&mut self) {
-    let response_result = self.send_request();
-    match response_result {
-      Ok(req_response) => {
-        self.request_response.set_response(req_response);
+    let (request, server) = {
+      let repo = self.repository.lock().unwrap();
+      (repo.get_active_request(), repo.get_server())
+    };
+    self.dispatch(request, server);
+  }
+
+  /// Starts sending `request` against `server` in the background. [`Home::poll_request`] picks up the outcome.
+  fn dispatch(&mut self, request: RequestInput, server: String) {
+    if self.notices.is_some() {
+      self.notify("Could not send the request", AppError::Other(String::from("A request is already running")));
+      return;
+    }
+    self.request_response.set_notification(None);
+    self.send_request(request, server);
+  }
+
+  /// Shows the notices of the running request, and once it is done its response, appending it to the history.
+  /// Anything that goes wrong is shown as a notification.
+  fn poll_request(&mut self) {
+    let notices = match &self.notices {
+      Some(notices) => notices,
+      None => return,
+    };
+    let result = match self.rx.as_ref().unwrap().try_recv() {
+      Ok((result, context)) => {
+        self.repository.lock().unwrap().set_env(context.env);
+        let saved = self.cookie_repository.lock().unwrap().set_jar(&context.server, context.cookie_jar);
+        if let Err(e) = saved {
+          self.notify("Could not write cookies", AppError::Io(e));
+        }
+        result
       },
-      Err(shoot) => {
-        panic!("ahhhh")
+      Err(_) => {
+        // the thread hands the notices sender back with the outcome, so it only goes away early when it failed
+        match notices.try_recv() {
+          Ok(notice) => self.request_response.set_info(Some(notice)),
+          Err(mpsc::TryRecvError::Empty) => {},
+          Err(mpsc::TryRecvError::Disconnected) => {
+            self.notices = None;
+            self.request_response.set_info(None);
+            let stopped = AppError::Other(String::from("The request stopped before a response came back"));
+            self.notify("Could not send the request", stopped);
+          },
+        }
+        return;
       },
+    };
+    self.notices = None;
+    self.request_response.set_info(None);
+    match result {
+      Ok(Outcome { sent, refresh_error }) => {
+        let max_body_bytes = self.config.as_ref().map(|c| c.config.history_max_body_bytes).unwrap_or(64 * 1024);
+        for exchange in sent.iter() {
+          self.timeline.record(exchange.clone());
+          let appended = self.history_repository.lock().unwrap().append(HistoryEntry::new(exchange, max_body_bytes));
+          if let Err(e) = appended {
+            self.notify("Could not write history", AppError::Io(e));
+          }
+        }
+        if let Some(last) = sent.into_iter().last() {
+          if last.response.status.is_none() {
+            self.notify("Could not send the request", AppError::Transport(last.response.body.clone()));
+          }
+          self.request_response.set_response(last.response);
+        }
+        if let Some(e) = refresh_error {
+          self.notify("Could not refresh the session", AppError::from(e));
+        }
+      },
+      Err(e) => self.notify("Could not send the request", AppError::from(e)),
     }
   }
 
-  fn send_request(&self) -> Result<ReqResponse, Box<dyn std::error::Error>> {
+  /// Logs `error` and shows it in the notification area until the next request or Esc.
+  fn notify(&mut self, context: &str, error: AppError) {
+    error!("{}: {:?}", context, error);
+    self.request_response.set_notification(Some(error.to_string()));
+  }
+
+  /// What a request against `server` is sent with: the environment, its cookies and the global client settings.
+  fn context_for(&self, server: String) -> http::Context {
+    let cookie_jar = self.cookie_repository.lock().unwrap().get_jar(&server);
+    let client = self.config.as_ref().map(|c| c.config.client.clone()).unwrap_or_default();
+    http::Context { server, cookie_jar, client, ..self.repository.lock().unwrap().get_context() }
+  }
+
+  fn send_request(&mut self, request: RequestInput, server: String) {
     let tx = self.tx.clone().unwrap();
-    let query = parse_query(&self.querystring.get_value()).unwrap();
-    let url = format!("{}{}?{}", &self.server.get_value(), &self.path.get_value(), query);
-    let headers: HeaderMap = self.parse_headers().unwrap();
-    let method = self.server.get_method().clone();
-    let payload: String = self.payload.get_value();
+    let (notices_tx, notices_rx) = mpsc::channel();
+    let mut context = http::Context { notices: Some(notices_tx), ..self.context_for(server) };
+    let refresh = self.repository.lock().unwrap().get_refresh();
 
     spawn_blocking(move || {
-      let client = reqwest::blocking::Client::new();
-      let req_builder: Option<reqwest::blocking::RequestBuilder>;
-      match method {
-        server::HttpMethod::GET => {
-          req_builder = Some(client.get(url).headers(headers));
-        },
-        server::HttpMethod::POST => {
-          req_builder = Some(client.post(url).headers(headers).body(payload));
-        },
-        server::HttpMethod::PUT => {
-          req_builder = Some(client.put(url).headers(headers).body(payload));
-        },
-        server::HttpMethod::DELETE => {
-          req_builder = Some(client.delete(url).headers(headers).body(payload));
-        },
-      }
-
-      let response_result = req_builder.unwrap().send();
-      match response_result {
-        Ok(response) => {
-          let headers = format!("{:?}", response.headers()).replace("\",", "\n").replace("{", " ").replace("}", "");
-          match response.text() {
-            Ok(text) => tx.send(ReqResponse { body: text, headers }).unwrap(),
-            Err(err) => tx.send(ReqResponse { body: err.to_string(), headers }).unwrap(),
-          }
-        },
-        Err(err) => tx.send(ReqResponse { body: err.to_string(), headers: String::from("") }).unwrap(),
-      }
+      let refresh = refresh.as_ref().map(|(request, trigger)| (request, trigger));
+      let result = refresh::send_with_refresh(&request, &mut context, refresh);
+      // the receiver only goes away with the app
+      let _ = tx.send((result, context));
     });
+    self.notices = Some(notices_rx);
+    self.request_response.set_info(Some(String::from("Sending…")));
+  }
 
-    loop {
-      let message = self.rx.as_ref().unwrap().try_recv();
-      match message {
-        Ok(msg) => return Ok(msg),
-        Err(mpsc::TryRecvError::Empty) => thread::sleep(time::Duration::from_millis(100)),
-        Err(mpsc::TryRecvError::Disconnected) => {
-          panic!("Disconnected ")
-        },
-      };
+  fn open_popup(&mut self, popup: MenuItem) {
+    if !is_popup(self.active_widget) {
+      self.last_widget = self.active_widget;
     }
+    self.active_widget = popup;
+  }
+
+  fn close_popup(&mut self) {
+    self.active_widget = self.last_widget;
   }
 
-  fn parse_headers(&self) -> Result<HeaderMap, Box<dyn std::error::Error>> {
-    let headers = self.headers.get_value();
-    if headers.len() == 0 {
-      return Ok(HeaderMap::new());
+  fn handle_timeline_key_events(&mut self, key: KeyEvent) {
+    match key.code {
+      KeyCode::Esc | KeyCode::Char('q') if self.timeline.is_showing_detail() => self.timeline.show_detail(false),
+      KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('T') => self.close_popup(),
+      // the entry is shown in the popup only, snapshots and pins keep working on the last response sent
+      KeyCode::Enter => self.timeline.show_detail(true),
+      _ => self.timeline.handle_key_events(key),
     }
-    // TODO: fix this
-    //let headers_with_env = replace_env_variables(headers, &self.local_storage.env).clone();
-    let headers_with_env = headers.clone();
-    let new_headers = Regex::new("\n+$").unwrap().replace_all(&headers_with_env, "");
-    // TODO: move regex compiltion out  of function
-    let valid_format = Regex::new(r"^((?>[^:\n\s]+\s?:[^:\n]+)\n?)+$").unwrap().is_match(&headers).unwrap();
-    if !valid_format {
-      return Err(Box::from("Not valid header format"));
+  }
+
+  fn handle_jwt_key_events(&mut self, key: KeyEvent) {
+    match key.code {
+      KeyCode::Enter | KeyCode::Esc if self.jwt_inspector.is_editing_secret() => self.jwt_inspector.edit_secret(false),
+      KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('J') if !self.jwt_inspector.is_editing_secret() => {
+        self.close_popup()
+      },
+      _ => self.jwt_inspector.handle_key_events(key),
     }
-    let new_headers: Vec<(String, String)> = headers
-      .split("\n")
-      .map(|h| {
-        let kv: Vec<&str> = h.split(":").map(|v| v.trim()).collect();
-        (kv[0].to_string(), kv[1].to_string())
-      })
-      .collect();
-    let mut header_map = HeaderMap::new();
-    for (name, value) in new_headers {
-      let header_name = HeaderName::from_bytes(name.as_bytes()).map_err(|e| Box::new(e))?;
-      let header_value = HeaderValue::from_str(&value)?;
-      header_map.insert(header_name, header_value);
+  }
+
+  fn open_preview(&mut self) {
+    let (request, server) = {
+      let repo = self.repository.lock().unwrap();
+      (repo.get_active_request(), repo.get_server())
+    };
+    self.preview.load(&request, &self.context_for(server));
+    self.open_popup(MenuItem::PreviewPopup);
+  }
+
+  fn handle_preview_key_events(&mut self, key: KeyEvent) {
+    match key.code {
+      KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('P') => self.close_popup(),
+      KeyCode::Enter => {
+        self.close_popup();
+        self.process_request();
+      },
+      _ => self.preview.handle_key_events(key),
+    }
+  }
+
+  fn handle_cookies_key_events(&mut self, key: KeyEvent) {
+    match key.code {
+      KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('C') if !self.cookies.is_editing() => self.close_popup(),
+      _ => self.cookies.handle_key_events(key),
+    }
+  }
+
+  /// Insecure TLS and expired env tokens, for the status bar.
+  fn status_warning(&self) -> Option<String> {
+    let (env, insecure) = {
+      let repo = self.repository.lock().unwrap();
+      (repo.get_env(), repo.is_insecure())
+    };
+    let expired = jwt::expired_env_tokens(&env, Utc::now());
+    let mut warnings = Vec::new();
+    if insecure {
+      warnings.push(String::from("INSECURE: TLS certificates are not verified"));
+    }
+    if !expired.is_empty() {
+      warnings.push(format!("Expired JWT in env: {}", expired.join(", ")));
+    }
+    if warnings.is_empty() {
+      None
+    } else {
+      Some(warnings.join(" | "))
+    }
+  }
+
+  fn handle_history_key_events(&mut self, key: KeyEvent) {
+    match key.code {
+      KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('H') => self.close_popup(),
+      KeyCode::Enter => {
+        if let Some(entry) = self.history.get_selected() {
+          self.request_response.set_response(entry.to_response());
+          self.close_popup();
+        }
+      },
+      KeyCode::Char('r') => {
+        if let Some(entry) = self.history.get_selected() {
+          self.close_popup();
+          let request = {
+            let repo = self.repository.lock().unwrap();
+            entry.replay(repo.get_request_list().iter().find(|saved| saved.id == entry.request.id))
+          };
+          let server = request.server.clone();
+          self.dispatch(request, server);
+        }
+      },
+      _ => self.history.handle_key_events(key),
+    }
+  }
+
+  /// Stores the last response of the active request as its golden snapshot.
+  fn record_snapshot(&mut self) {
+    let response = match self.request_response.get_response() {
+      Some(response) => response,
+      None => return,
+    };
+    let name = snapshot::name_for(&self.repository.lock().unwrap().get_active_request());
+    if let Err(e) = snapshot::save(&name, &snapshot::Snapshot::from_response(response)) {
+      self.notify(&format!("Could not save snapshot {}", name), AppError::Io(e));
+    }
+  }
+
+  fn toggle_snapshot_diff(&mut self) {
+    if self.request_response.is_showing_snapshot_diff() {
+      self.request_response.set_snapshot_diff(None);
+      return;
+    }
+    let response = match self.request_response.get_response() {
+      Some(response) => response,
+      None => return,
+    };
+    let request = self.repository.lock().unwrap().get_active_request();
+    match snapshot::load(&snapshot::name_for(&request)) {
+      Ok(Some(expected)) => {
+        let actual = snapshot::Snapshot::from_response(response);
+        let changes = snapshot::compare(&expected, &actual, &snapshot::ignore_rules(&request));
+        self.request_response.set_snapshot_diff(Some(changes));
+      },
+      Ok(None) => self.notify("Snapshot diff", AppError::Other(String::from("No snapshot recorded for this request"))),
+      Err(e) => self.notify("Could not load snapshot", AppError::Io(e)),
     }
-    Ok(header_map)
   }
 
   fn focus_next_widget(&mut self) {
@@ -269,11 +452,37 @@ impl Component for Home {
   fn handle_key_events(&mut self, key: KeyEvent) -> Result<Option<Action>> {
     self.last_events.push(key.clone());
 
+    match self.active_widget {
+      MenuItem::HistoryPopup => {
+        self.handle_history_key_events(key);
+        return Ok(Some(Action::Update));
+      },
+      MenuItem::TimelinePopup => {
+        self.handle_timeline_key_events(key);
+        return Ok(Some(Action::Update));
+      },
+      MenuItem::JwtPopup => {
+        self.handle_jwt_key_events(key);
+        return Ok(Some(Action::Update));
+      },
+      MenuItem::CookiesPopup => {
+        self.handle_cookies_key_events(key);
+        return Ok(Some(Action::Update));
+      },
+      MenuItem::PreviewPopup => {
+        self.handle_preview_key_events(key);
+        return Ok(Some(Action::Update));
+      },
+      _ => {},
+    }
+
     match key {
       // match global keybindings
       KeyEvent { modifiers: KeyModifiers::CONTROL, code: KeyCode::Char('s'), kind: _, state: _ } => {
-        let repo = self.repository.lock().unwrap();
-        repo.save();
+        let saved = self.repository.lock().unwrap().save();
+        if let Err(e) = saved {
+          self.notify("Could not save cartero.json", AppError::Io(e));
+        }
       },
       KeyEvent { modifiers: _, code: KeyCode::Tab, kind: _, state: _ } => self.focus_next_widget(),
       KeyEvent { modifiers: _, code: KeyCode::BackTab, kind: _, state: _ } => self.focus_previous_widget(),
@@ -282,9 +491,33 @@ impl Component for Home {
         // if no global match, match mode specific keybindings
         Mode::Normal => match key {
           KeyEvent { modifiers: _, code: KeyCode::Char('i'), kind: _, state: _ } => self.mode = Mode::Insert,
+          KeyEvent { modifiers: _, code: KeyCode::Esc, kind: _, state: _ } => {
+            self.request_response.set_notification(None)
+          },
           KeyEvent { modifiers: _, code: KeyCode::Char('q'), kind: _, state: _ } => {
             return Ok(Some(Action::Quit));
           },
+          KeyEvent { modifiers: _, code: KeyCode::Char('S'), kind: _, state: _ } => self.record_snapshot(),
+          KeyEvent { modifiers: _, code: KeyCode::Char('D'), kind: _, state: _ } => self.toggle_snapshot_diff(),
+          KeyEvent { modifiers: _, code: KeyCode::Char('H'), kind: _, state: _ } => {
+            self.history.reset();
+            self.open_popup(MenuItem::HistoryPopup);
+          },
+          KeyEvent { modifiers: _, code: KeyCode::Char('T'), kind: _, state: _ } => {
+            self.timeline.reset();
+            self.open_popup(MenuItem::TimelinePopup);
+          },
+          KeyEvent { modifiers: _, code: KeyCode::Char('J'), kind: _, state: _ } => {
+            let env = self.repository.lock().unwrap().get_env();
+            self.jwt_inspector.load(&env, self.request_response.get_response());
+            self.open_popup(MenuItem::JwtPopup);
+          },
+          KeyEvent { modifiers: _, code: KeyCode::Char('C'), kind: _, state: _ } => {
+            let server = self.repository.lock().unwrap().get_server();
+            self.cookies.load(server);
+            self.open_popup(MenuItem::CookiesPopup);
+          },
+          KeyEvent { modifiers: _, code: KeyCode::Char('P'), kind: _, state: _ } => self.open_preview(),
           KeyEvent { modifiers: _, code: KeyCode::Char(c), kind: _, state: _ } => {
             self.get_active_widget().handle_normal_key_events(key)
           },
@@ -303,7 +536,10 @@ impl Component for Home {
   fn update(&mut self, action: Action) -> Result<Option<Action>> {
     match action {
       Action::Tick => self.tick(),
-      Action::Render => self.render_tick(),
+      Action::Render => {
+        self.render_tick();
+        self.poll_request();
+      },
       Action::ToggleShowHelp if self.mode == Mode::Normal => self.show_help = !self.show_help,
       Action::EditInput => {
         let mut temp_file = tempfile::NamedTempFile::new().unwrap();
@@ -406,8 +642,29 @@ impl Component for Home {
 
     let _ = self.headers.draw(f, request_data_chunk[2], is_focused(self.active_widget, MenuItem::Headers));
 
+    let warning = self.status_warning();
+    self.request_response.set_warning(warning);
     let _ = self.request_response.draw(f, request_chunk[2], footer, is_focused(self.active_widget, MenuItem::JsonPath));
 
+    match self.active_widget {
+      MenuItem::HistoryPopup => {
+        let _ = self.history.draw(f, rect);
+      },
+      MenuItem::TimelinePopup => {
+        let _ = self.timeline.draw(f, rect);
+      },
+      MenuItem::JwtPopup => {
+        let _ = self.jwt_inspector.draw(f, rect);
+      },
+      MenuItem::CookiesPopup => {
+        let _ = self.cookies.draw(f, rect);
+      },
+      MenuItem::PreviewPopup => {
+        let _ = self.preview.draw(f, rect);
+      },
+      _ => {},
+    }
+
     Ok(())
   }
 }
@@ -424,6 +681,11 @@ enum MenuItem {
   JsonPath,
   ServerListPopup,
   ParsingRulesPopup,
+  HistoryPopup,
+  TimelinePopup,
+  JwtPopup,
+  CookiesPopup,
+  PreviewPopup,
 }
 
 impl MenuItem {
@@ -454,6 +716,11 @@ impl From<MenuItem> for usize {
       MenuItem::JsonPath => 6,
       MenuItem::ServerListPopup => 0,
       MenuItem::ParsingRulesPopup => 0,
+      MenuItem::HistoryPopup => 0,
+      MenuItem::TimelinePopup => 0,
+      MenuItem::JwtPopup => 0,
+      MenuItem::CookiesPopup => 0,
+      MenuItem::PreviewPopup => 0,
     }
   }
 }
@@ -477,34 +744,17 @@ fn is_focused(active_item: MenuItem, item: MenuItem) -> bool {
   active_item == item
 }
 
-fn parse_query(query: &str) -> Result<String, Box<dyn std::error::Error>> {
-  if query.len() == 0 {
-    return Ok("".into());
-  }
-  let query = Regex::new("\n+$").unwrap().replace_all(query, "");
-  let valid_format = Regex::new(r"^((?>[^=\n\s]+=[^=\n]+)\n?)+$").unwrap().is_match(&query).unwrap();
-  if !valid_format {
-    return Err(Box::from("Not valid query format"));
-  }
-  let query = Regex::new("\n").unwrap().replace_all(&query, "&");
-  Ok(query.to_string())
-}
-
-fn replace_env_variables(input: &str, values: &HashMap<String, String>) -> String {
-  let re = Regex::new(r"\{\{([a-zA-Z_-]+)\}\}").unwrap();
-  let mut output = input.to_string();
-
-  for capture in re.captures_iter(input) {
-    let capture_matches = capture.unwrap();
-    let key = capture_matches.get(1).unwrap().as_str();
-    let value = match values.get(key) {
-      Some(v) => v.to_string(),
-      None => String::new(),
-    };
-    output = output.replace(capture_matches.get(0).unwrap().as_str(), &value);
-  }
-
-  output
+fn is_popup(item: MenuItem) -> bool {
+  matches!(
+    item,
+    MenuItem::ServerListPopup
+      | MenuItem::ParsingRulesPopup
+      | MenuItem::HistoryPopup
+      | MenuItem::TimelinePopup
+      | MenuItem::JwtPopup
+      | MenuItem::CookiesPopup
+      | MenuItem::PreviewPopup
+  )
 }
 
 /// helper function to create a centered rect using up certain percentage of the available rect `r`
@@ -0,0 +1,127 @@
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use super::{centered_rect, subcomponent::Subcomponent, Frame};
+use crate::{
+  http::{
+    preview::{self, Preview, PreviewLine, Segment},
+    Context,
+  },
+  repository::local_storage::RequestInput,
+};
+
+/// Shows the active request fully resolved before it is sent, with unresolved variables and invalid lines marked.
+#[derive(Default)]
+pub struct RequestPreview {
+  preview: Option<Preview>,
+  scroll: u16,
+}
+
+impl RequestPreview {
+  pub fn load(&mut self, request: &RequestInput, context: &Context) {
+    self.preview = Some(preview::preview(request, context));
+    self.scroll = 0;
+  }
+
+  pub fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    let popup = centered_rect(80, 80, rect);
+    f.render_widget(Clear, popup);
+    let text = match &self.preview {
+      Some(preview) => describe(preview),
+      None => Text::from(""),
+    };
+    let paragraph = Paragraph::new(text).wrap(Wrap { trim: false }).scroll((self.scroll, 0)).block(
+      Block::default()
+        .borders(Borders::ALL)
+        .style(self.get_style(true))
+        .title("Preview (enter: send, j/k: scroll, esc: close)")
+        .border_type(BorderType::Plain),
+    );
+    f.render_widget(paragraph, popup);
+    Ok(())
+  }
+}
+
+fn describe(preview: &Preview) -> Text<'static> {
+  let error_style = Style::default().fg(Color::Red);
+  let heading = |title: &str| Line::styled(title.to_string(), Style::default().add_modifier(Modifier::BOLD));
+  let problems = preview.problems();
+  let mut lines = if problems.is_empty() {
+    vec![Line::styled("Ready to send", Style::default().fg(Color::Green))]
+  } else {
+    let mut lines =
+      vec![Line::styled(format!("{} problem(s)", problems.len()), error_style.add_modifier(Modifier::BOLD))];
+    lines.extend(problems.into_iter().map(|problem| Line::styled(format!("  {}", problem), error_style)));
+    lines
+  };
+
+  lines.push(Line::from(""));
+  lines.push(match &preview.url {
+    Ok(url) => Line::from(vec![
+      Span::styled(preview.method.to_string(), preview.method.get_style()),
+      Span::raw(format!(" {}", url)),
+    ]),
+    Err(err) => Line::styled(format!("{} {}", preview.method.to_string(), err), error_style),
+  });
+
+  for (title, section) in [("Query", &preview.query), ("Headers", &preview.headers)] {
+    lines.push(Line::from(""));
+    lines.push(heading(title));
+    lines.extend(section.iter().map(preview_line));
+  }
+  if !preview.added_headers.is_empty() || preview.signed_on_send.is_some() {
+    lines.push(Line::from(""));
+    lines.push(heading("Added by auth and cookies"));
+    let added_style = Style::default().fg(Color::Yellow);
+    lines.extend(preview.added_headers.iter().map(|header| Line::styled(header.clone(), added_style)));
+    if let Some(signature) = preview.signed_on_send {
+      lines.push(Line::styled(format!("{} is computed when sending", signature), Style::default().fg(Color::Gray)));
+    }
+  }
+  lines.push(Line::from(""));
+  lines.push(heading("Body"));
+  lines.extend(preview.body.iter().map(preview_line));
+  Text::from(lines)
+}
+
+/// The line with its unresolved variables highlighted, followed by its error.
+fn preview_line(line: &PreviewLine) -> Line<'static> {
+  let unresolved_style = Style::default().fg(Color::Red).add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+  let mut spans: Vec<Span> = line
+    .segments
+    .iter()
+    .map(|segment| match segment {
+      Segment::Text(text) => Span::raw(text.clone()),
+      Segment::Unresolved(name) => Span::styled(format!("{{{{{}}}}}", name), unresolved_style),
+    })
+    .collect();
+  if let Some(error) = &line.error {
+    spans.push(Span::styled(format!("  ✗ {}", error), Style::default().fg(Color::Red)));
+  }
+  Line::from(spans)
+}
+
+impl Subcomponent for RequestPreview {
+  fn handle_normal_key_events(&mut self, key: KeyEvent) {
+    self.handle_key_events(key);
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) {
+    match key {
+      KeyEvent { modifiers: _, code: KeyCode::Char('j') | KeyCode::Down, kind: _, state: _ } => {
+        self.scroll = self.scroll.saturating_add(1)
+      },
+      KeyEvent { modifiers: _, code: KeyCode::Char('k') | KeyCode::Up, kind: _, state: _ } => {
+        self.scroll = self.scroll.saturating_sub(1)
+      },
+      _ => {},
+    }
+  }
+
+  fn push(&mut self, _c: char) {}
+
+  fn pop(&mut self) {}
+
+  fn clear(&mut self) {}
+}
@@ -33,7 +33,7 @@ impl RequestList {
       self.req_list_state.select(Some(selected.min(requests.len() - 1))); // Keep in bounds
     }
 
-    let requests_list = render_reqs(requests, style);
+    let requests_list = render_reqs(&repo, requests, style);
     f.render_stateful_widget(requests_list, rect, &mut self.req_list_state);
 
     Ok(())
@@ -58,9 +58,21 @@ impl RequestList {
   fn delete_request(&mut self) {
     self.repository.lock().unwrap().delete_request();
   }
+
+  fn toggle_refresh_request(&mut self) {
+    self.repository.lock().unwrap().toggle_refresh_request();
+  }
+
+  fn toggle_cookies(&mut self) {
+    self.repository.lock().unwrap().toggle_cookies();
+  }
+
+  fn toggle_redirects(&mut self) {
+    self.repository.lock().unwrap().toggle_redirects();
+  }
 }
 
-fn render_reqs<'a>(user_reqs: &Vec<RequestInput>, style: Style) -> List<'a> {
+fn render_reqs<'a>(repo: &LocalStorageRepository, user_reqs: &Vec<RequestInput>, style: Style) -> List<'a> {
   let requests = Block::default()
     .borders(Borders::ALL)
     .style(Style::default().fg(Color::LightCyan))
@@ -71,11 +83,21 @@ fn render_reqs<'a>(user_reqs: &Vec<RequestInput>, style: Style) -> List<'a> {
   let items: Vec<_> = user_reqs
     .iter()
     .map(|req| {
-      ListItem::new(Line::from(vec![
+      let mut spans = vec![
         Span::styled(req.method.to_string(), req.method.get_style()),
         Span::styled(" ", Style::default()),
         Span::styled(req.path.clone(), Style::default()),
-      ]))
+      ];
+      if repo.is_refresh_request(&req.id) {
+        spans.push(Span::styled(" [refresh]", Style::default().fg(Color::Magenta)));
+      }
+      if !req.cookies {
+        spans.push(Span::styled(" [no cookies]", Style::default().fg(Color::DarkGray)));
+      }
+      if req.client.max_redirects == Some(0) {
+        spans.push(Span::styled(" [no redirects]", Style::default().fg(Color::DarkGray)));
+      }
+      ListItem::new(Line::from(spans))
     })
     .collect();
 
@@ -97,6 +119,9 @@ impl Subcomponent for RequestList {
       KeyEvent { modifiers: _, code: KeyCode::Char('a'), kind: _, state: _ } => self.add_request(),
       KeyEvent { modifiers: _, code: KeyCode::Char('c'), kind: _, state: _ } => self.duplicate_request(),
       KeyEvent { modifiers: _, code: KeyCode::Char('d'), kind: _, state: _ } => self.delete_request(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('r'), kind: _, state: _ } => self.toggle_refresh_request(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('x'), kind: _, state: _ } => self.toggle_cookies(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('f'), kind: _, state: _ } => self.toggle_redirects(),
       _ => {},
     }
   }
@@ -1,21 +1,63 @@
 use std::{
   io::{Read, Write},
   process::{Command, Stdio},
+  sync::mpsc,
+  thread,
 };
 
-use super::{subcomponent::Subcomponent, Component, Frame, MenuItem, ReqResponse};
+use super::{subcomponent::Subcomponent, Component, Frame, MenuItem};
+use chrono::{Duration, Utc};
 use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
 use ratatui::{prelude::*, widgets::*};
 use serde::Serialize;
 use serde_json::Value;
 use tracing::{debug, error, info, trace, warn};
 
+use crate::{
+  diff::{self, Change, DiffRow, RowTag},
+  http::{timing::Timing, tls::TlsDetails, wire, ReqResponse, TlsProbe},
+  jwt, snapshot, utils,
+};
+
 pub struct RequestResponse {
   response_headers: String,
   response_body: String,
   response_body_last: String,
   body_filter: String,
   jq_is_installed: bool,
+  last_response: Option<ReqResponse>,
+  snapshot_diff: Option<Vec<Change>>,
+  pinned_response: Option<ReqResponse>,
+  compare_mode: Option<CompareMode>,
+  /// First row of the comparison shown
+  compare_scroll: u16,
+  warning: Option<String>,
+  /// The last error, shown in the status bar over any warning
+  notification: Option<String>,
+  /// What the running request has to tell, shown in the status bar over any warning
+  info: Option<String>,
+  header_tab: HeaderTab,
+  /// Redirect of the chain whose headers and body are shown, the final response when `None`
+  selected_hop: Option<usize>,
+  /// Shows the request and response as HTTP/1.1 transcripts in place of the payload
+  show_wire: bool,
+  /// The handshake describing the TLS connection of the last response, while it runs
+  tls_handshake: Option<mpsc::Receiver<Result<TlsDetails, String>>>,
+}
+
+/// What the pane above the payload shows.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum HeaderTab {
+  Headers,
+  Details,
+  Redirects,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum CompareMode {
+  Lines,
+  Structure,
 }
 
 impl RequestResponse {
@@ -26,46 +68,220 @@ impl RequestResponse {
       response_body_last: String::from(""),
       body_filter: String::from(""),
       jq_is_installed: jq_is_installed(),
+      last_response: None,
+      snapshot_diff: None,
+      pinned_response: None,
+      compare_mode: None,
+      compare_scroll: 0,
+      warning: None,
+      notification: None,
+      info: None,
+      header_tab: HeaderTab::Headers,
+      selected_hop: None,
+      show_wire: false,
+      tls_handshake: None,
+    }
+  }
+
+  /// Shows `warning` in the status bar instead of the app name.
+  pub fn set_warning(&mut self, warning: Option<String>) {
+    self.warning = warning;
+  }
+
+  pub fn set_notification(&mut self, notification: Option<String>) {
+    self.notification = notification;
+  }
+
+  pub fn set_info(&mut self, info: Option<String>) {
+    self.info = info;
+  }
+
+  /// Keeps `response` as the left side of the comparison view.
+  pub fn pin_response(&mut self, response: ReqResponse) {
+    self.pinned_response = Some(response);
+  }
+
+  /// Cycles the comparison view between off, line-level and JSON-structural diff.
+  fn toggle_compare(&mut self) {
+    self.compare_scroll = 0;
+    if self.pinned_response.is_none() || self.last_response.is_none() {
+      self.compare_mode = None;
+      return;
+    }
+    self.compare_mode = match self.compare_mode {
+      None => Some(CompareMode::Lines),
+      Some(CompareMode::Lines) => Some(CompareMode::Structure),
+      Some(CompareMode::Structure) => None,
+    };
+  }
+
+  /// The rows of the comparison, with the mode they were diffed in: the JSON-structural diff falls back to lines when
+  /// either body is not JSON.
+  fn compare_rows(&self) -> (Vec<DiffRow>, &'static str) {
+    let (pinned, current) = match (&self.pinned_response, &self.last_response) {
+      (Some(pinned), Some(current)) => (pinned, current),
+      _ => return (vec![], "lines"),
+    };
+    let lines = || diff::side_by_side(&pretty_body(&pinned.body), &pretty_body(&current.body));
+    if self.compare_mode != Some(CompareMode::Structure) {
+      return (lines(), "lines");
     }
+    match (serde_json::from_str::<Value>(&pinned.body), serde_json::from_str::<Value>(&current.body)) {
+      (Ok(old), Ok(new)) => (diff::structural_rows(&diff::json_diff(&old, &new)), "json"),
+      _ => (lines(), "lines, not JSON"),
+    }
+  }
+
+  pub fn get_response(&self) -> Option<&ReqResponse> {
+    self.last_response.as_ref()
+  }
+
+  /// Replaces the payload pane with the given snapshot diff, or goes back to the body when `None`.
+  pub fn set_snapshot_diff(&mut self, changes: Option<Vec<Change>>) {
+    self.snapshot_diff = changes;
+  }
+
+  pub fn is_showing_snapshot_diff(&self) -> bool {
+    self.snapshot_diff.is_some()
   }
 
   pub fn set_response(&mut self, req_response: ReqResponse) {
-    self.response_headers = req_response.headers;
-
-    match serde_json::from_str::<Value>(&req_response.body) {
-      Ok(json) => match serde_json::to_string_pretty(&json) {
-        Ok(pretty_json) => {
-          self.response_body = pretty_json;
-        },
-        Err(e) => {
-          self.response_body = format!("JSON serialization error: {}", e);
-        },
-      },
-      Err(e) => {
-        self.response_body = req_response.body.clone();
-      },
+    self.last_response = Some(req_response.clone());
+    self.snapshot_diff = None;
+    self.selected_hop = None;
+    self.tls_handshake = None;
+    self.show_body(&req_response);
+    self.probe_tls();
+  }
+
+  /// Starts the handshake describing the TLS connection of the last response, while the details tab is open and it has
+  /// not been described yet.
+  fn probe_tls(&mut self) {
+    if self.header_tab != HeaderTab::Details || self.tls_handshake.is_some() {
+      return;
+    }
+    let target = match &self.last_response {
+      Some(ReqResponse { tls: None, tls_probe: Some(TlsProbe::Direct(target)), .. }) => target.clone(),
+      _ => return,
+    };
+    let (tx, rx) = mpsc::channel();
+    thread::spawn(move || {
+      let _ = tx.send(target.handshake());
+    });
+    self.tls_handshake = Some(rx);
+  }
+
+  /// Keeps the result of the TLS handshake once it is done.
+  fn poll_tls(&mut self) {
+    let result = match self.tls_handshake.as_ref().map(|rx| rx.try_recv()) {
+      Some(Ok(result)) => result,
+      Some(Err(mpsc::TryRecvError::Disconnected)) => Err(String::from("The TLS handshake stopped without a result")),
+      _ => return,
+    };
+    self.tls_handshake = None;
+    if let Some(response) = &mut self.last_response {
+      response.tls = Some(result);
+    }
+  }
+
+  /// The selected redirect, or the final response.
+  fn shown_response(&self) -> Option<&ReqResponse> {
+    let response = self.last_response.as_ref()?;
+    match self.selected_hop {
+      Some(hop) => response.redirects.get(hop).map(|redirect| &redirect.response),
+      None => Some(response),
+    }
+  }
+
+  fn copy_wire(&self) {
+    if let Some(response) = self.shown_response() {
+      match utils::copy_to_clipboard(&wire::transcript(response)) {
+        Ok(()) => info!("Copied the wire transcript"),
+        Err(e) => error!("Could not copy the wire transcript: {:?}", e),
+      }
     }
+  }
 
-    self.response_body_last = req_response.body;
+  fn show_body(&mut self, response: &ReqResponse) {
+    self.response_headers = response.headers.clone();
+    self.response_body = pretty_body(&response.body);
+    self.response_body_last = response.body.clone();
+  }
+
+  /// Moves along the redirect chain, `forward` towards the final response, showing the headers and body of the hop.
+  fn select_hop(&mut self, forward: bool) {
+    let response = match &self.last_response {
+      Some(response) if !response.redirects.is_empty() => response.clone(),
+      _ => return,
+    };
+    let last = response.redirects.len();
+    let current = self.selected_hop.unwrap_or(last);
+    let selected = if forward { (current + 1).min(last) } else { current.saturating_sub(1) };
+    self.selected_hop = (selected < last).then_some(selected);
+    match self.selected_hop {
+      Some(hop) => self.show_body(&response.redirects[hop].response),
+      None => self.show_body(&response),
+    }
   }
 
   pub fn draw(&mut self, f: &mut Frame<'_>, results_rect: Rect, footer_rec: Rect, is_focused: bool) -> Result<()> {
     let request_result_chunk = Layout::default()
       .direction(Direction::Vertical)
-      .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+      .constraints([Constraint::Length(4), Constraint::Percentage(35), Constraint::Min(5)].as_ref())
       .split(results_rect);
 
-    let result_headers = Paragraph::new(AsRef::<str>::as_ref(&self.response_headers))
-      .style(Style::default().fg(Color::Green))
-      .alignment(Alignment::Left)
-      .block(
+    let timing = self.last_response.as_ref().and_then(|response| response.timing.as_ref().map(|t| (response, t)));
+    let waterfall = match timing {
+      Some((response, timing)) => {
+        let width = request_result_chunk[0].width.saturating_sub(2) as usize;
+        Paragraph::new(waterfall(timing, width)).block(
+          Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title(format!("Timing ({}ms)", response.elapsed_ms))
+            .border_type(BorderType::Plain),
+        )
+      },
+      None => Paragraph::new("No timing yet").style(Style::default().fg(Color::DarkGray)).block(
         Block::default()
           .borders(Borders::ALL)
           .style(Style::default().fg(Color::White))
-          .title("Response Headers")
+          .title("Timing")
           .border_type(BorderType::Plain),
-      );
-    f.render_widget(result_headers, request_result_chunk[0]);
+      ),
+    };
+    f.render_widget(waterfall, request_result_chunk[0]);
+
+    let tab_style = |active: bool| {
+      if active {
+        Style::default().add_modifier(Modifier::BOLD)
+      } else {
+        Style::default().fg(Color::DarkGray)
+      }
+    };
+    let redirects = self.last_response.as_ref().map(|response| response.redirects.len()).unwrap_or(0);
+    let title = Line::from(vec![
+      Span::styled("Response Headers", tab_style(self.header_tab == HeaderTab::Headers)),
+      Span::raw(" | "),
+      Span::styled("Details", tab_style(self.header_tab == HeaderTab::Details)),
+      Span::raw(" | "),
+      Span::styled(format!("Redirects ({})", redirects), tab_style(self.header_tab == HeaderTab::Redirects)),
+      Span::raw(" (t: switch, [/]: hop)"),
+    ]);
+    self.poll_tls();
+    let text = match (&self.last_response, self.header_tab) {
+      (Some(response), HeaderTab::Details) => connection_details(response),
+      (Some(response), HeaderTab::Redirects) => redirect_chain(response, self.selected_hop),
+      _ => Text::styled(self.response_headers.clone(), Style::default().fg(Color::Green)),
+    };
+    let result_headers = Paragraph::new(text).alignment(Alignment::Left).wrap(Wrap { trim: false }).block(
+      Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default().fg(Color::White))
+        .title(title)
+        .border_type(BorderType::Plain),
+    );
+    f.render_widget(result_headers, request_result_chunk[1]);
 
     let result_payload = Paragraph::new(AsRef::<str>::as_ref(&self.response_body))
       .style(Style::default().fg(Color::LightCyan))
@@ -74,24 +290,42 @@ impl RequestResponse {
         Block::default()
           .borders(Borders::ALL)
           .style(Style::default().fg(Color::White))
-          .title("Response Payload")
+          .title(match self.selected_hop {
+            Some(hop) => format!("Response Payload (redirect {})", hop + 1),
+            None => String::from("Response Payload"),
+          })
           .border_type(BorderType::Plain),
       );
-    f.render_widget(result_payload, request_result_chunk[1]);
+    match (&self.snapshot_diff, self.compare_mode) {
+      (Some(changes), _) => f.render_widget(render_snapshot_diff(changes), request_result_chunk[2]),
+      (None, Some(_)) => self.draw_compare(f, request_result_chunk[2]),
+      (None, None) if self.show_wire => self.draw_wire(f, request_result_chunk[2]),
+      (None, None) => f.render_widget(result_payload, request_result_chunk[2]),
+    }
 
     let lower_bar_chunks = Layout::default()
       .direction(Direction::Horizontal)
       .constraints([Constraint::Length(80), Constraint::Min(50)].as_ref())
       .split(footer_rec);
 
-    let copyright = Paragraph::new("HTTP Request Explorer")
-      .style(Style::default().fg(Color::LightCyan))
+    let (status, status_style, title) = match (&self.notification, &self.warning) {
+      (Some(error), _) => (error.as_str(), Style::default().fg(Color::Red), "Error (esc: dismiss)"),
+      (None, _) if self.info.is_some() => {
+        (self.info.as_deref().unwrap_or_default(), Style::default().fg(Color::LightYellow), "Request")
+      },
+      (None, Some(warning)) => {
+        (warning.as_str(), Style::default().fg(Color::Red).add_modifier(Modifier::BOLD), "Copyright")
+      },
+      (None, None) => ("HTTP Request Explorer", Style::default().fg(Color::LightCyan), "Copyright"),
+    };
+    let copyright = Paragraph::new(status)
+      .style(status_style)
       .alignment(Alignment::Center)
       .block(
         Block::default()
           .borders(Borders::ALL)
           .style(Style::default().fg(Color::White))
-          .title("Copyright")
+          .title(title)
           .border_type(BorderType::Plain),
       );
     f.render_widget(copyright, lower_bar_chunks[0]);
@@ -117,6 +351,53 @@ impl RequestResponse {
     Ok(())
   }
 
+  fn draw_compare(&mut self, f: &mut Frame<'_>, rect: Rect) {
+    let panes = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+      .split(rect);
+    let (rows, mode_title) = self.compare_rows();
+    self.compare_scroll = self.compare_scroll.min(rows.len().saturating_sub(1) as u16);
+
+    let left: Vec<Line> = rows.iter().map(|row| diff_line(&row.left, row.tag)).collect();
+    let right: Vec<Line> = rows.iter().map(|row| diff_line(&row.right, row.tag)).collect();
+    for (lines, title, pane) in [(left, "Pinned", panes[0]), (right, "Current", panes[1])] {
+      let paragraph = Paragraph::new(lines).alignment(Alignment::Left).scroll((self.compare_scroll, 0)).block(
+        Block::default()
+          .borders(Borders::ALL)
+          .style(Style::default().fg(Color::White))
+          .title(format!("{} ({}, j/k: scroll)", title, mode_title))
+          .border_type(BorderType::Plain),
+      );
+      f.render_widget(paragraph, pane);
+    }
+  }
+
+  fn draw_wire(&self, f: &mut Frame<'_>, rect: Rect) {
+    let panes = Layout::default()
+      .direction(Direction::Horizontal)
+      .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+      .split(rect);
+    let (request, response) = match self.shown_response() {
+      Some(response) => {
+        (response.raw_request.clone().unwrap_or_else(|| String::from("Request not recorded")), wire::response(response))
+      },
+      None => (String::new(), String::new()),
+    };
+    let sides = [(request, "Request (wire)", panes[0]), (response, "Response (wire, y: copy)", panes[1])];
+    for (text, title, pane) in sides {
+      let paragraph =
+        Paragraph::new(text).style(Style::default().fg(Color::LightCyan)).wrap(Wrap { trim: false }).block(
+          Block::default()
+            .borders(Borders::ALL)
+            .style(Style::default().fg(Color::White))
+            .title(title)
+            .border_type(BorderType::Plain),
+        );
+      f.render_widget(paragraph, pane);
+    }
+  }
+
   fn parse_with_serde(&mut self) {
     match serde_json::from_str::<Value>(&self.response_body_last) {
       Ok(json_value) => {
@@ -175,6 +456,184 @@ impl RequestResponse {
   }
 }
 
+fn pretty_body(body: &str) -> String {
+  match serde_json::from_str::<Value>(body) {
+    Ok(json) => match serde_json::to_string_pretty(&json) {
+      Ok(pretty_json) => pretty_json,
+      Err(e) => format!("JSON serialization error: {}", e),
+    },
+    Err(_) => body.to_string(),
+  }
+}
+
+/// The phases of a request as a bar `width` cells wide, over a legend with their durations.
+fn waterfall(timing: &Timing, width: usize) -> Text<'static> {
+  let color = |phase: &str| match phase {
+    "dns" => Color::Cyan,
+    "connect" => Color::Yellow,
+    "tls" => Color::Magenta,
+    "ttfb" => Color::Green,
+    _ => Color::Blue,
+  };
+  let phases = timing.waterfall(width);
+  let bar: Vec<Span> =
+    phases.iter().map(|(name, _, cells)| Span::styled(" ".repeat(*cells), Style::default().bg(color(name)))).collect();
+  let legend: Vec<Span> = phases
+    .iter()
+    .map(|(name, ms, _)| Span::styled(format!("■ {} {}ms  ", name, ms), Style::default().fg(color(name))))
+    .collect();
+  Text::from(vec![Line::from(bar), Line::from(legend)])
+}
+
+/// Remote address, proxy and, for HTTPS, the server's certificate and ALPN protocol of the request's own connection,
+/// with the protocol version and cipher of a separate handshake.
+fn connection_details(response: &ReqResponse) -> Text<'static> {
+  let now = Utc::now();
+  let label = |key: &str, value: String| {
+    Line::from(vec![Span::styled(format!("{}: ", key), Style::default().fg(Color::Gray)), Span::raw(value)])
+  };
+  let mut lines = vec![
+    label("Remote", response.remote_addr.clone().unwrap_or_else(|| String::from("unknown"))),
+    label("Proxy", response.proxy.clone().unwrap_or_else(|| String::from("direct"))),
+    label("Time", format!("{}ms", response.elapsed_ms)),
+  ];
+  let probe_note = Line::styled(
+    "Version and cipher from a separate handshake with the same address, which may reach another server than the \
+     request did",
+    Style::default().fg(Color::DarkGray),
+  );
+  match (&response.tls, &response.tls_probe) {
+    (Some(Ok(tls)), _) => {
+      lines.push(label("TLS", format!("{}, {}", tls.version, tls.cipher)));
+      lines.push(probe_note);
+    },
+    (Some(Err(err)), _) => {
+      lines.push(Line::styled(format!("TLS: {}", err), Style::default().fg(Color::Red)));
+      lines.push(probe_note);
+    },
+    (None, Some(TlsProbe::Proxied)) => {
+      lines.push(label("TLS", String::from("version and cipher not available through a proxy")));
+    },
+    (None, Some(TlsProbe::Direct(_))) => {
+      lines.push(label("TLS", String::from("handshaking separately for the version and cipher…")));
+    },
+    (None, None) if response.tls_connection.is_none() => lines.push(label("TLS", String::from("none"))),
+    (None, None) => {},
+  }
+  let connection = match (&response.tls_connection, &response.tls_probe) {
+    (Some(connection), _) => connection,
+    (None, Some(TlsProbe::Proxied)) => {
+      // reqwest tells the certificate through SOCKS proxies, but not through HTTP proxy tunnels
+      lines.push(label("Certificate", String::from("not available through this proxy")));
+      return Text::from(lines);
+    },
+    (None, _) => return Text::from(lines),
+  };
+  let alpn = match (&connection.alpn, &response.tls_probe) {
+    (Some(alpn), _) => alpn.clone(),
+    (None, Some(TlsProbe::Proxied)) => String::from("not known through a proxy"),
+    (None, _) => String::from("none"),
+  };
+  lines.push(label("ALPN", alpn));
+  let (verification, verification_color) = match connection.verified {
+    true => ("ok", Color::Green),
+    false => ("not checked, insecure", Color::Yellow),
+  };
+  lines.push(Line::from(vec![
+    Span::styled("Verification: ", Style::default().fg(Color::Gray)),
+    Span::styled(verification, Style::default().fg(verification_color)),
+  ]));
+  let cert = &connection.certificate;
+  lines.push(Line::from(""));
+  lines.push(Line::styled("Certificate", Style::default().add_modifier(Modifier::BOLD)));
+  lines.push(label("Subject", cert.subject.clone()));
+  lines.push(label("Issuer", cert.issuer.clone()));
+  if !cert.sans.is_empty() {
+    lines.push(label("SANs", cert.sans.join(", ")));
+  }
+  lines.push(label("Not before", jwt::describe_timestamp("nbf", cert.not_before, now)));
+  let expiry_style = match cert.not_after - now {
+    left if left <= Duration::zero() => Style::default().fg(Color::Red),
+    left if left <= Duration::days(14) => Style::default().fg(Color::Yellow),
+    _ => Style::default().fg(Color::Green),
+  };
+  lines.push(Line::from(vec![
+    Span::styled("Not after: ", Style::default().fg(Color::Gray)),
+    Span::styled(jwt::describe_timestamp("exp", cert.not_after, now), expiry_style),
+  ]));
+  Text::from(lines)
+}
+
+/// Every redirect with where it pointed, then the final response, with the selected one's headers below.
+fn redirect_chain(response: &ReqResponse, selected: Option<usize>) -> Text<'static> {
+  if response.redirects.is_empty() {
+    return Text::styled("No redirects", Style::default().fg(Color::DarkGray));
+  }
+  let status = |response: &ReqResponse| response.status.map(|s| s.to_string()).unwrap_or_else(|| String::from("ERR"));
+  let style = |idx: Option<usize>| {
+    if idx == selected {
+      Style::default().fg(Color::Yellow).add_modifier(Modifier::BOLD)
+    } else {
+      Style::default()
+    }
+  };
+  let mut lines: Vec<Line> = response
+    .redirects
+    .iter()
+    .enumerate()
+    .map(|(idx, hop)| {
+      let text =
+        format!("{}. {} {} → {} {}", idx + 1, hop.method.to_string(), hop.url, status(&hop.response), hop.location);
+      Line::styled(text, style(Some(idx)))
+    })
+    .collect();
+  lines.push(Line::styled(format!("{}. {} final", lines.len() + 1, status(response)), style(None)));
+  lines.push(Line::from(""));
+  let headers = match selected {
+    Some(hop) => &response.redirects[hop].response.headers,
+    None => &response.headers,
+  };
+  lines.extend(headers.lines().map(|line| Line::styled(line.to_string(), Style::default().fg(Color::Green))));
+  Text::from(lines)
+}
+
+fn diff_line(text: &Option<String>, tag: RowTag) -> Line<'static> {
+  let style = match (text, tag) {
+    (None, _) | (_, RowTag::Equal) => Style::default().fg(Color::LightCyan),
+    (_, RowTag::Added) => Style::default().fg(Color::Green),
+    (_, RowTag::Removed) => Style::default().fg(Color::Red),
+    (_, RowTag::Changed) => Style::default().fg(Color::Yellow),
+  };
+  Line::from(Span::styled(text.clone().unwrap_or_default(), style))
+}
+
+fn render_snapshot_diff(changes: &[Change]) -> Paragraph<'_> {
+  let title = if changes.is_empty() {
+    String::from("Snapshot Diff: matches")
+  } else {
+    format!("Snapshot Diff: {} change(s)", changes.len())
+  };
+  let lines: Vec<Line> = changes
+    .iter()
+    .map(|change| {
+      let color = match change {
+        Change::Added { .. } => Color::Green,
+        Change::Removed { .. } => Color::Red,
+        Change::Changed { .. } => Color::Yellow,
+      };
+      Line::from(Span::styled(snapshot::format_change(change), Style::default().fg(color)))
+    })
+    .collect();
+
+  Paragraph::new(lines).alignment(Alignment::Left).block(
+    Block::default()
+      .borders(Borders::ALL)
+      .style(Style::default().fg(Color::White))
+      .title(title)
+      .border_type(BorderType::Plain),
+  )
+}
+
 fn jq_is_installed() -> bool {
   debug!("Checking if jq is installed..."); // Add debug logging
 
@@ -197,6 +656,36 @@ fn jq_is_installed() -> bool {
 }
 
 impl Subcomponent for RequestResponse {
+  fn handle_normal_key_events(&mut self, key: KeyEvent) {
+    match key {
+      KeyEvent { modifiers: _, code: KeyCode::Char('p'), kind: _, state: _ } => {
+        if let Some(response) = self.last_response.clone() {
+          self.pin_response(response);
+        }
+      },
+      KeyEvent { modifiers: _, code: KeyCode::Char('c'), kind: _, state: _ } => self.toggle_compare(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('j'), kind: _, state: _ } if self.compare_mode.is_some() => {
+        self.compare_scroll = self.compare_scroll.saturating_add(1)
+      },
+      KeyEvent { modifiers: _, code: KeyCode::Char('k'), kind: _, state: _ } if self.compare_mode.is_some() => {
+        self.compare_scroll = self.compare_scroll.saturating_sub(1)
+      },
+      KeyEvent { modifiers: _, code: KeyCode::Char('t'), kind: _, state: _ } => {
+        self.header_tab = match self.header_tab {
+          HeaderTab::Headers => HeaderTab::Details,
+          HeaderTab::Details => HeaderTab::Redirects,
+          HeaderTab::Redirects => HeaderTab::Headers,
+        };
+        self.probe_tls();
+      },
+      KeyEvent { modifiers: _, code: KeyCode::Char('w'), kind: _, state: _ } => self.show_wire = !self.show_wire,
+      KeyEvent { modifiers: _, code: KeyCode::Char('y'), kind: _, state: _ } => self.copy_wire(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('['), kind: _, state: _ } => self.select_hop(false),
+      KeyEvent { modifiers: _, code: KeyCode::Char(']'), kind: _, state: _ } => self.select_hop(true),
+      _ => {},
+    }
+  }
+
   //fn handle_key_events(&mut self, key: crossterm::event::KeyEvent) {
   //  self.handle_default_key_events(key);
   //  if self.jq_is_installed {
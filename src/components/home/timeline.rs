@@ -0,0 +1,173 @@
+use color_eyre::eyre::Result;
+use crossterm::event::{KeyCode, KeyEvent};
+use ratatui::{prelude::*, widgets::*};
+
+use chrono::{DateTime, Local};
+
+use super::{centered_rect, history::status_style, subcomponent::Subcomponent, Frame};
+use crate::http::dispatch::Sent;
+
+/// Chronological list of every request sent during the current session, across all saved requests. Requests are kept
+/// as they were sent, not truncated nor redacted like in the history file.
+#[derive(Default)]
+pub struct Timeline {
+  entries: Vec<(DateTime<Local>, Sent)>,
+  list_state: ListState,
+  show_detail: bool,
+}
+
+impl Timeline {
+  pub fn record(&mut self, sent: Sent) {
+    self.entries.push((Local::now(), sent));
+  }
+
+  pub fn reset(&mut self) {
+    self.show_detail = false;
+    if !self.entries.is_empty() {
+      self.list_state.select(Some(self.entries.len() - 1));
+    }
+  }
+
+  pub fn get_selected(&self) -> Option<&Sent> {
+    self.list_state.selected().and_then(|idx| self.entries.get(idx)).map(|(_, sent)| sent)
+  }
+
+  pub fn is_showing_detail(&self) -> bool {
+    self.show_detail
+  }
+
+  pub fn show_detail(&mut self, show: bool) {
+    self.show_detail = show && self.get_selected().is_some();
+  }
+
+  pub fn draw(&mut self, f: &mut Frame<'_>, rect: Rect) -> Result<()> {
+    let popup = centered_rect(80, 70, rect);
+    f.render_widget(Clear, popup);
+
+    if self.show_detail {
+      if let Some(sent) = self.get_selected() {
+        draw_detail(f, popup, sent);
+        return Ok(());
+      }
+    }
+
+    let items: Vec<ListItem> = self
+      .entries
+      .iter()
+      .map(|(timestamp, sent)| {
+        let response = &sent.response;
+        let status = response.status.map(|s| s.to_string()).unwrap_or_else(|| String::from("ERR"));
+        let size = response.body.len();
+        ListItem::new(Line::from(vec![
+          Span::styled(timestamp.format("%H:%M:%S ").to_string(), Style::default().fg(Color::Gray)),
+          Span::styled(format!("{:<7}", sent.request.method.to_string()), sent.request.method.get_style()),
+          Span::styled(format!("{:<4}", status), status_style(response.status)),
+          Span::styled(format!("{:>6}ms {:>8}B ", response.elapsed_ms, size), Style::default().fg(Color::Gray)),
+          Span::styled(sent.url.clone(), Style::default().fg(Color::LightCyan)),
+        ]))
+      })
+      .collect();
+
+    let list = List::new(items)
+      .block(
+        Block::default()
+          .borders(Borders::ALL)
+          .style(self.get_style(true))
+          .title("Session Timeline (enter: open, esc: close)")
+          .border_type(BorderType::Plain),
+      )
+      .highlight_style(Style::default().bg(Color::Yellow).add_modifier(Modifier::BOLD));
+    f.render_stateful_widget(list, popup, &mut self.list_state);
+
+    Ok(())
+  }
+
+  fn next(&mut self) {
+    if self.entries.is_empty() {
+      return;
+    }
+    let selected = self.list_state.selected().unwrap_or(0);
+    self.list_state.select(Some((selected + 1).min(self.entries.len() - 1)));
+  }
+
+  fn previous(&mut self) {
+    let selected = self.list_state.selected().unwrap_or(0);
+    self.list_state.select(Some(selected.saturating_sub(1)));
+  }
+}
+
+fn draw_detail(f: &mut Frame<'_>, rect: Rect, sent: &Sent) {
+  let chunks = Layout::default()
+    .direction(Direction::Horizontal)
+    .constraints([Constraint::Percentage(50), Constraint::Percentage(50)].as_ref())
+    .split(rect);
+
+  let (request, response) = (&sent.request, &sent.response);
+  let request_text = format!(
+    "{} {}\n\nHeaders:\n{}\n\nPayload:\n{}",
+    request.method.to_string(),
+    sent.url,
+    sent.headers,
+    request.payload
+  );
+  let status = response.status.map(|s| s.to_string()).unwrap_or_else(|| String::from("ERR"));
+  let proxy = response.proxy.as_deref().unwrap_or("direct");
+  let phases = match &response.timing {
+    Some(timing) => {
+      let phases: Vec<String> = timing.phases().iter().map(|(name, ms)| format!("{} {}ms", name, ms)).collect();
+      format!(" ({})", phases.join(", "))
+    },
+    None => String::new(),
+  };
+  let redirects: String = response
+    .redirects
+    .iter()
+    .map(|hop| {
+      let status = hop.response.status.map(|s| s.to_string()).unwrap_or_default();
+      format!("\n  {} {} -> {} {}", hop.method.to_string(), hop.url, status, hop.location)
+    })
+    .collect();
+  let redirects = if redirects.is_empty() { redirects } else { format!("\nRedirects:{}", redirects) };
+  let response_text = format!(
+    "Status: {}\nTime: {}ms{}\nProxy: {}\nSize: {}B{}\n\nHeaders:\n{}\n\nBody:\n{}",
+    status,
+    response.elapsed_ms,
+    phases,
+    proxy,
+    response.body.len(),
+    redirects,
+    response.headers,
+    response.body
+  );
+
+  for (text, title, pane) in [(request_text, "Request", chunks[0]), (response_text, "Response", chunks[1])] {
+    let paragraph = Paragraph::new(text).style(Style::default().fg(Color::LightCyan)).wrap(Wrap { trim: false }).block(
+      Block::default()
+        .borders(Borders::ALL)
+        .style(Style::default().fg(Color::White))
+        .title(format!("{} (esc: back)", title))
+        .border_type(BorderType::Plain),
+    );
+    f.render_widget(paragraph, pane);
+  }
+}
+
+impl Subcomponent for Timeline {
+  fn handle_normal_key_events(&mut self, key: KeyEvent) {
+    self.handle_key_events(key);
+  }
+
+  fn handle_key_events(&mut self, key: KeyEvent) {
+    match key {
+      KeyEvent { modifiers: _, code: KeyCode::Char('j') | KeyCode::Down, kind: _, state: _ } => self.next(),
+      KeyEvent { modifiers: _, code: KeyCode::Char('k') | KeyCode::Up, kind: _, state: _ } => self.previous(),
+      _ => {},
+    }
+  }
+
+  fn push(&mut self, c: char) {}
+
+  fn pop(&mut self) {}
+
+  fn clear(&mut self) {}
+}
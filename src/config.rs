@@ -11,16 +11,36 @@ use serde::{
 };
 use serde_json::Value as JsonValue;
 
-use crate::{action::Action, app::Mode};
+use crate::{action::Action, app::Mode, http::client::ClientSettings};
 
 const CONFIG: &str = include_str!("../.config/config.json5");
 
-#[derive(Clone, Debug, Deserialize, Default)]
+#[derive(Clone, Debug, Deserialize)]
 pub struct AppConfig {
   #[serde(default)]
   pub _data_dir: PathBuf,
   #[serde(default)]
   pub _config_dir: PathBuf,
+  #[serde(default = "default_history_max_body_bytes")]
+  pub history_max_body_bytes: usize,
+  /// Client settings every request starts from
+  #[serde(default)]
+  pub client: ClientSettings,
+}
+
+fn default_history_max_body_bytes() -> usize {
+  64 * 1024
+}
+
+impl Default for AppConfig {
+  fn default() -> Self {
+    AppConfig {
+      _data_dir: PathBuf::default(),
+      _config_dir: PathBuf::default(),
+      history_max_body_bytes: default_history_max_body_bytes(),
+      client: ClientSettings::default(),
+    }
+  }
 }
 
 #[derive(Clone, Debug, Default, Deserialize)]
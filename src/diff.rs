@@ -0,0 +1,229 @@
+use serde_json::Value;
+use similar::{DiffTag, TextDiff};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Change {
+  Added { pointer: String, value: Value },
+  Removed { pointer: String, value: Value },
+  Changed { pointer: String, old: Value, new: Value },
+}
+
+impl Change {
+  pub fn pointer(&self) -> &str {
+    match self {
+      Change::Added { pointer, .. } | Change::Removed { pointer, .. } | Change::Changed { pointer, .. } => pointer,
+    }
+  }
+}
+
+/// Walks both values and reports every key that was added, removed or changed, addressed by JSON pointer.
+pub fn json_diff(old: &Value, new: &Value) -> Vec<Change> {
+  let mut changes = Vec::new();
+  diff_at(String::new(), old, new, &mut changes);
+  changes
+}
+
+fn diff_at(pointer: String, old: &Value, new: &Value, changes: &mut Vec<Change>) {
+  match (old, new) {
+    (Value::Object(old_map), Value::Object(new_map)) => {
+      for (key, old_value) in old_map {
+        let child = format!("{}/{}", pointer, escape_token(key));
+        match new_map.get(key) {
+          Some(new_value) => diff_at(child, old_value, new_value, changes),
+          None => changes.push(Change::Removed { pointer: child, value: old_value.clone() }),
+        }
+      }
+      for (key, new_value) in new_map {
+        if !old_map.contains_key(key) {
+          let child = format!("{}/{}", pointer, escape_token(key));
+          changes.push(Change::Added { pointer: child, value: new_value.clone() });
+        }
+      }
+    },
+    (Value::Array(old_items), Value::Array(new_items)) => {
+      for (idx, old_value) in old_items.iter().enumerate() {
+        let child = format!("{}/{}", pointer, idx);
+        match new_items.get(idx) {
+          Some(new_value) => diff_at(child, old_value, new_value, changes),
+          None => changes.push(Change::Removed { pointer: child, value: old_value.clone() }),
+        }
+      }
+      for (idx, new_value) in new_items.iter().enumerate().skip(old_items.len()) {
+        changes.push(Change::Added { pointer: format!("{}/{}", pointer, idx), value: new_value.clone() });
+      }
+    },
+    _ => {
+      if old != new {
+        changes.push(Change::Changed { pointer, old: old.clone(), new: new.clone() });
+      }
+    },
+  }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RowTag {
+  Equal,
+  Added,
+  Removed,
+  Changed,
+}
+
+/// One row of a side-by-side diff. A missing side means the line only exists on the other one.
+#[derive(Clone, Debug, PartialEq)]
+pub struct DiffRow {
+  pub tag: RowTag,
+  pub left: Option<String>,
+  pub right: Option<String>,
+}
+
+/// Line-level diff of two texts, aligned so that row `n` of each side can be rendered next to each other.
+pub fn side_by_side(old: &str, new: &str) -> Vec<DiffRow> {
+  let old_lines: Vec<&str> = old.lines().collect();
+  let new_lines: Vec<&str> = new.lines().collect();
+  let diff = TextDiff::from_slices(&old_lines, &new_lines);
+  let mut rows = Vec::new();
+
+  for op in diff.ops() {
+    let (tag, old_range, new_range) = op.as_tag_tuple();
+    let left = &old_lines[old_range];
+    let right = &new_lines[new_range];
+    let row_tag = match tag {
+      DiffTag::Equal => RowTag::Equal,
+      DiffTag::Delete => RowTag::Removed,
+      DiffTag::Insert => RowTag::Added,
+      DiffTag::Replace => RowTag::Changed,
+    };
+    for idx in 0..left.len().max(right.len()) {
+      let tag = match (left.get(idx), right.get(idx)) {
+        (Some(_), None) => RowTag::Removed,
+        (None, Some(_)) => RowTag::Added,
+        _ => row_tag,
+      };
+      let (left, right) = (left.get(idx).map(|l| l.to_string()), right.get(idx).map(|l| l.to_string()));
+      rows.push(DiffRow { tag, left, right });
+    }
+  }
+  rows
+}
+
+/// Lays out a structural diff as side-by-side rows, with the pointer repeated on both sides.
+pub fn structural_rows(changes: &[Change]) -> Vec<DiffRow> {
+  changes
+    .iter()
+    .map(|change| match change {
+      Change::Added { pointer, value } => {
+        DiffRow { tag: RowTag::Added, left: None, right: Some(format!("{}: {}", pointer, value)) }
+      },
+      Change::Removed { pointer, value } => {
+        DiffRow { tag: RowTag::Removed, left: Some(format!("{}: {}", pointer, value)), right: None }
+      },
+      Change::Changed { pointer, old, new } => DiffRow {
+        tag: RowTag::Changed,
+        left: Some(format!("{}: {}", pointer, old)),
+        right: Some(format!("{}: {}", pointer, new)),
+      },
+    })
+    .collect()
+}
+
+/// Removes the node addressed by `pointer` from `value`. A `*` token matches every key or index at that level.
+pub fn remove_pointer(value: &mut Value, pointer: &str) {
+  let pointer = pointer.trim();
+  if pointer.is_empty() || !pointer.starts_with('/') {
+    return;
+  }
+  let tokens: Vec<String> = pointer[1..].split('/').map(unescape_token).collect();
+  remove_tokens(value, &tokens);
+}
+
+fn remove_tokens(value: &mut Value, tokens: &[String]) {
+  let (head, rest) = match tokens.split_first() {
+    Some(split) => split,
+    None => return,
+  };
+  match value {
+    Value::Object(map) => {
+      if rest.is_empty() {
+        if head == "*" {
+          map.clear();
+        } else {
+          map.remove(head);
+        }
+      } else if head == "*" {
+        map.values_mut().for_each(|v| remove_tokens(v, rest));
+      } else if let Some(v) = map.get_mut(head) {
+        remove_tokens(v, rest);
+      }
+    },
+    Value::Array(items) => {
+      if head == "*" {
+        if rest.is_empty() {
+          items.clear();
+        } else {
+          items.iter_mut().for_each(|v| remove_tokens(v, rest));
+        }
+      } else if let Ok(idx) = head.parse::<usize>() {
+        if rest.is_empty() {
+          if idx < items.len() {
+            items.remove(idx);
+          }
+        } else if let Some(v) = items.get_mut(idx) {
+          remove_tokens(v, rest);
+        }
+      }
+    },
+    _ => {},
+  }
+}
+
+fn escape_token(token: &str) -> String {
+  token.replace('~', "~0").replace('/', "~1")
+}
+
+fn unescape_token(token: &str) -> String {
+  token.replace("~1", "/").replace("~0", "~")
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use serde_json::json;
+
+  use super::*;
+
+  #[test]
+  fn test_json_diff_reports_added_removed_and_changed() {
+    let old = json!({ "id": 1, "name": "goku", "tags": ["a"] });
+    let new = json!({ "id": 2, "tags": ["a", "b"], "email": "goku@local.com" });
+    let changes = json_diff(&old, &new);
+    assert_eq!(changes, vec![
+      Change::Changed { pointer: "/id".into(), old: json!(1), new: json!(2) },
+      Change::Removed { pointer: "/name".into(), value: json!("goku") },
+      Change::Added { pointer: "/tags/1".into(), value: json!("b") },
+      Change::Added { pointer: "/email".into(), value: json!("goku@local.com") },
+    ]);
+  }
+
+  #[test]
+  fn test_json_diff_equal_values() {
+    let value = json!({ "a": { "b": [1, 2, { "c": null }] } });
+    assert!(json_diff(&value, &value).is_empty());
+  }
+
+  #[test]
+  fn test_side_by_side_aligns_rows() {
+    let rows = side_by_side("a\nb\nc\n", "a\nB\nc\nd\n");
+    let tags: Vec<RowTag> = rows.iter().map(|r| r.tag).collect();
+    assert_eq!(tags, vec![RowTag::Equal, RowTag::Changed, RowTag::Equal, RowTag::Added]);
+    assert_eq!(rows[1], DiffRow { tag: RowTag::Changed, left: Some("b".into()), right: Some("B".into()) });
+    assert_eq!(rows[3].left, None);
+  }
+
+  #[test]
+  fn test_remove_pointer_with_wildcard() {
+    let mut value = json!({ "items": [{ "id": 1, "v": "x" }, { "id": 2, "v": "y" }], "a/b": 1 });
+    remove_pointer(&mut value, "/items/*/id");
+    remove_pointer(&mut value, "/a~1b");
+    assert_eq!(value, json!({ "items": [{ "v": "x" }, { "v": "y" }] }));
+  }
+}
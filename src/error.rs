@@ -0,0 +1,78 @@
+use std::io;
+
+use thiserror::Error;
+
+/// What can go wrong preparing, sending and saving requests. These are shown in the notification area and never end
+/// the session.
+#[derive(Debug, Error)]
+pub enum Error {
+  #[error("Invalid header line `{line}`: {reason}")]
+  InvalidHeader { line: String, reason: String },
+  #[error("Invalid query line `{0}`, expected `name=value`")]
+  InvalidQuery(String),
+  #[error("Not in the env: {}", .0.iter().map(|name| format!("{{{{{}}}}}", name)).collect::<Vec<_>>().join(", "))]
+  UnresolvedVariables(Vec<String>),
+  #[error("Request failed: {0}")]
+  Transport(String),
+  #[error("I/O error: {0}")]
+  Io(#[from] io::Error),
+  #[error("{0}")]
+  Other(String),
+}
+
+impl From<crate::http::Error> for Error {
+  /// Recovers the kind of a boxed error, as most of the request pipeline passes errors on boxed.
+  fn from(error: crate::http::Error) -> Self {
+    let error = match error.downcast::<Error>() {
+      Ok(error) => return *error,
+      Err(error) => error,
+    };
+    let error = match error.downcast::<io::Error>() {
+      Ok(error) => return Error::Io(*error),
+      Err(error) => error,
+    };
+    match error.downcast::<reqwest::Error>() {
+      Ok(error) => Error::Transport(error.to_string()),
+      Err(error) => Error::Other(error.to_string()),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::{http, repository::local_storage::RequestInput};
+
+  #[test]
+  fn test_boxed_errors_keep_their_kind() {
+    let boxed: http::Error = Box::new(Error::InvalidQuery(String::from("broken")));
+    assert_eq!(Error::from(boxed).to_string(), "Invalid query line `broken`, expected `name=value`");
+
+    let boxed: http::Error = Box::new(io::Error::new(io::ErrorKind::PermissionDenied, "cartero.json"));
+    assert!(matches!(Error::from(boxed), Error::Io(_)));
+
+    let boxed: http::Error = Box::from("Invalid host mapping");
+    assert!(matches!(Error::from(boxed), Error::Other(message) if message == "Invalid host mapping"));
+
+    let missing = Error::UnresolvedVariables(vec![String::from("token"), String::from("tenant")]);
+    assert_eq!(missing.to_string(), "Not in the env: {{token}}, {{tenant}}");
+  }
+
+  #[test]
+  fn test_typos_in_a_request_are_errors() {
+    let context = http::Context { server: String::from("http://localhost"), ..Default::default() };
+    let prepare = |request: RequestInput| Error::from(http::prepare(&request, &context).unwrap_err());
+
+    let typo = prepare(RequestInput { headers: String::from("Accept application/json"), ..Default::default() });
+    assert!(matches!(typo, Error::InvalidHeader { line, .. } if line == "Accept application/json"));
+    let typo = prepare(RequestInput { query: String::from("page=1\nlimit 10"), ..Default::default() });
+    assert!(matches!(typo, Error::InvalidQuery(line) if line == "limit 10"));
+    let typo = prepare(RequestInput { path: String::from("/users/{{user_id}}"), ..Default::default() });
+    assert!(matches!(typo, Error::UnresolvedVariables(names) if names == vec![String::from("user_id")]));
+    // payloads are sent as they are, as they may be templates themselves
+    let template = RequestInput { payload: String::from(r#"{"query": "{{name}}"}"#), ..Default::default() };
+    assert!(http::prepare(&template, &context).is_ok());
+  }
+}
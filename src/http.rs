@@ -0,0 +1,506 @@
+use std::{
+  collections::HashMap,
+  net::{SocketAddr, ToSocketAddrs},
+  sync::mpsc,
+  time::{Duration, Instant},
+};
+
+use chrono::Utc;
+use fancy_regex::Regex;
+use lazy_static::lazy_static;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, COOKIE};
+use serde::{Deserialize, Serialize};
+
+use crate::{
+  auth::Auth,
+  components::home::server::HttpMethod,
+  error::Error as AppError,
+  repository::{cookies::CookieJar, local_storage::RequestInput},
+};
+
+pub mod client;
+pub mod direct;
+pub mod dispatch;
+pub mod dns;
+pub mod preview;
+pub mod proxy;
+pub mod redirect;
+#[cfg(test)]
+pub(crate) mod test_server;
+pub mod timing;
+pub mod tls;
+pub mod unix;
+pub mod wire;
+
+use client::ClientSettings;
+use dns::HostMapping;
+use proxy::ProxySettings;
+use redirect::Redirect;
+use timing::Timing;
+use tls::{ConnectionTls, TlsDetails, TlsSettings};
+
+lazy_static! {
+  /// A `{{var}}` placeholder, with the variable name as its first group.
+  pub static ref PLACEHOLDER: Regex = Regex::new(r"\{\{([a-zA-Z0-9_-]+)\}\}").unwrap();
+}
+
+/// A request with its query and headers already parsed, ready to be dispatched.
+#[derive(Clone, Debug, Default)]
+pub struct PreparedRequest {
+  pub method: HttpMethod,
+  pub url: String,
+  pub headers: HeaderMap,
+  pub body: String,
+  /// The request's client settings, already merged with the global ones
+  pub client: ClientSettings,
+  /// Proxies with the OS ones and env variables resolved
+  pub proxy: ProxySettings,
+  /// TLS settings with env variables resolved
+  pub tls: TlsSettings,
+  /// Hosts to connect to at a fixed address
+  pub resolve: Vec<HostMapping>,
+  /// Path of the Unix socket to send the request over, for `unix://` servers
+  pub unix_socket: Option<String>,
+}
+
+#[derive(Default, Clone, Debug, Serialize, Deserialize)]
+pub struct ReqResponse {
+  pub status: Option<u16>,
+  pub headers: String,
+  pub body: String,
+  #[serde(default)]
+  pub elapsed_ms: u64,
+  /// The proxy the request went through, without its password
+  #[serde(default)]
+  pub proxy: Option<String>,
+  /// The IP and port connected to, the proxy's when there was one
+  #[serde(default)]
+  pub remote_addr: Option<String>,
+  /// For HTTPS, the server's certificate as the request's own connection presented it
+  #[serde(default)]
+  pub tls_connection: Option<ConnectionTls>,
+  /// For HTTPS, the protocol version and cipher a separate handshake negotiated once [`ReqResponse::tls_probe`] ran,
+  /// or why it failed
+  #[serde(default)]
+  pub tls: Option<Result<TlsDetails, String>>,
+  /// For HTTPS, how to learn the protocol version and cipher, which the client does not expose for the request's own
+  /// connection
+  #[serde(skip)]
+  pub tls_probe: Option<TlsProbe>,
+  #[serde(default)]
+  pub timing: Option<Timing>,
+  /// Redirects followed before this response, in order
+  #[serde(default)]
+  pub redirects: Vec<Redirect>,
+  /// The request as it went over the wire, see [`wire::request`]. Never saved, as it has the credentials in clear
+  #[serde(skip)]
+  pub raw_request: Option<String>,
+  #[serde(skip)]
+  pub header_map: HeaderMap,
+}
+
+pub type Error = Box<dyn std::error::Error + Send + Sync>;
+
+/// How to learn the protocol version and cipher of an HTTPS response.
+#[derive(Clone, Debug)]
+pub enum TlsProbe {
+  /// With a separate handshake, see [`TlsTarget::handshake`]
+  Direct(TlsTarget),
+  /// Not at all, the request went through a proxy and a handshake made without it could tell something else
+  Proxied,
+}
+
+/// The server an HTTPS request connected to, with the settings it connected with.
+#[derive(Clone, Debug)]
+pub struct TlsTarget {
+  pub host: String,
+  pub addr: SocketAddr,
+  pub alpn: Vec<&'static str>,
+  pub tls: TlsSettings,
+}
+
+impl TlsTarget {
+  /// Opens a new connection to the address the request went to and tells its protocol version and cipher. Behind a
+  /// load balancer it may reach another server than the request did.
+  pub fn handshake(&self) -> Result<TlsDetails, String> {
+    self.tls.inspect(&self.host, self.addr, &self.alpn).map_err(|e| e.to_string())
+  }
+}
+
+/// Collection-wide settings a request is sent with.
+#[derive(Clone, Debug, Default)]
+pub struct Context {
+  pub server: String,
+  pub env: HashMap<String, String>,
+  pub default_auth: Option<Auth>,
+  /// Cookies of the environment, sent with and updated by requests that use cookies
+  pub cookie_jar: CookieJar,
+  /// Global client settings from the config
+  pub client: ClientSettings,
+  /// Proxies of the active environment
+  pub proxy: ProxySettings,
+  /// CA, insecure mode and client certificate of the active environment
+  pub tls: TlsSettings,
+  /// `host:port -> address` mappings of the active environment
+  pub resolve: Vec<String>,
+  /// Where to send what the user has to see while the request runs, like the URL of an OAuth2 authorization
+  pub notices: Option<mpsc::Sender<String>>,
+}
+
+impl PreparedRequest {
+  /// The body as it is sent: GET requests are sent without one.
+  pub fn sent_body(&self) -> &str {
+    match self.method {
+      HttpMethod::GET => "",
+      _ => &self.body,
+    }
+  }
+}
+
+impl Context {
+  pub fn auth_for<'a>(&'a self, request: &'a RequestInput) -> Option<&'a Auth> {
+    request.auth.as_ref().or(self.default_auth.as_ref())
+  }
+
+  /// A client for `url` with the environment's proxies, TLS settings and host mappings, for requests made outside
+  /// of [`send`], like fetching OAuth2 tokens.
+  pub fn client_for(&self, url: &str) -> Result<reqwest::blocking::Client, Error> {
+    let url = url::Url::parse(url)?;
+    let proxy = self.proxy.resolve_with_os(&self.env);
+    let mappings = dns::parse_mappings(&self.resolve, &self.env)?;
+    let builder = self.client.builder(&proxy, &self.tls.resolve(&self.env))?;
+    let resolved = Some(&url).filter(|url| proxy.proxy_for(url).is_none()).and_then(|url| resolve(url, &mappings));
+    let builder = match resolved {
+      Some((host, addrs, _)) => builder.resolve_to_addrs(&host, &addrs),
+      None => builder,
+    };
+    Ok(builder.build()?)
+  }
+}
+
+/// Substitutes `{{var}}` placeholders from the env, parses the query and headers and applies the auth. Signing auth
+/// types sign the request last, once nothing else changes it.
+pub fn prepare(request: &RequestInput, context: &Context) -> Result<PreparedRequest, Error> {
+  let mut prepared = prepare_unsigned(request, context)?;
+  if let Some(auth) = context.auth_for(request) {
+    auth.sign(&mut prepared, &context.env)?;
+  }
+  Ok(prepared)
+}
+
+/// [`prepare`] without signing, which depends on the time and uses up Digest nonce counts. Fails on variables the env
+/// lacks, except in the payload, where `{{...}}` may well be meant literally, as in templates.
+pub fn prepare_unsigned(request: &RequestInput, context: &Context) -> Result<PreparedRequest, Error> {
+  let fields = [&context.server, &request.path, &request.query, &request.headers];
+  let mut unresolved: Vec<String> = fields.iter().flat_map(|field| unresolved_variables(field, &context.env)).collect();
+  unresolved.sort();
+  unresolved.dedup();
+  if !unresolved.is_empty() {
+    return Err(AppError::UnresolvedVariables(unresolved).into());
+  }
+  substitute(request, context)
+}
+
+/// Builds the request with unresolved variables left empty.
+fn substitute(request: &RequestInput, context: &Context) -> Result<PreparedRequest, Error> {
+  let env = &context.env;
+  let mut query = parse_query(&replace_env_variables(&request.query, env))?;
+  let mut headers = parse_headers(&replace_env_variables(&request.headers, env))?;
+  if let Some(auth) = context.auth_for(request) {
+    auth.apply(&mut headers, &mut query, env)?;
+  }
+  let url = replace_env_variables(&format!("{}{}?{}", context.server, request.path, query), env);
+  if request.cookies {
+    add_cookies(&mut headers, &url, &context.cookie_jar)?;
+  }
+  let body = replace_env_variables(&request.payload, env);
+  let client = request.client.or(&context.client);
+  let proxy = context.proxy.resolve_with_os(env);
+  let tls = context.tls.resolve(env);
+  let resolve = dns::parse_mappings(&context.resolve, env)?;
+  let unix_socket = replace_env_variables(&context.server, env).strip_prefix(unix::SCHEME).map(String::from);
+  Ok(PreparedRequest { method: request.method, url, headers, body, client, proxy, tls, resolve, unix_socket })
+}
+
+/// Adds the jar's cookies for `url` to the `Cookie` header, after any cookies the request sets itself.
+pub(crate) fn add_cookies(headers: &mut HeaderMap, url: &str, jar: &CookieJar) -> Result<(), Error> {
+  let url = url::Url::parse(url)?;
+  if let Some(cookies) = jar.header_for(&url, Utc::now()) {
+    let value = match headers.get(COOKIE).map(|v| v.to_str()).transpose()? {
+      Some(existing) => format!("{}; {}", existing, cookies),
+      None => cookies,
+    };
+    headers.insert(COOKIE, HeaderValue::from_str(&value)?);
+  }
+  Ok(())
+}
+
+/// Sends the request with a blocking client. Must not be called from an async context.
+pub fn send(request: PreparedRequest) -> ReqResponse {
+  if let Some(socket) = &request.unix_socket {
+    return send_unix(socket, &request);
+  }
+  let url = url::Url::parse(&request.url).ok();
+  match url.filter(|url| matches!(url.scheme(), "http" | "https") && request.proxy.proxy_for(url).is_none()) {
+    Some(url) => send_direct(&url, &request),
+    None => send_proxied(request),
+  }
+}
+
+/// Sends a request that goes straight to the server over a connection of its own, see [`direct::send`], so every
+/// phase is timed on it.
+fn send_direct(url: &url::Url, request: &PreparedRequest) -> ReqResponse {
+  let started = Instant::now();
+  let raw_request = Some(wire::request(request));
+  let failed = |err: String| ReqResponse {
+    body: format!("error sending request for url ({}): {}", url, err),
+    elapsed_ms: started.elapsed().as_millis() as u64,
+    raw_request: raw_request.clone(),
+    ..Default::default()
+  };
+  if let Err(err) = request.proxy.validate() {
+    return failed(err.to_string());
+  }
+  // resolved up front so DNS can be timed and hosts mapped
+  let (addrs, dns) = match (resolve(url, &request.resolve), url.host(), url.port_or_known_default()) {
+    (Some((_, addrs, dns)), _, _) => (addrs, dns),
+    (None, Some(url::Host::Ipv4(ip)), Some(port)) => (vec![SocketAddr::new(ip.into(), port)], None),
+    (None, Some(url::Host::Ipv6(ip)), Some(port)) => (vec![SocketAddr::new(ip.into(), port)], None),
+    _ => return failed(format!("could not resolve {}", url.host_str().unwrap_or_default())),
+  };
+  let response = match direct::send(request, url, &addrs) {
+    Ok(response) => response,
+    Err(err) => return failed(err.to_string()),
+  };
+  let elapsed_ms = started.elapsed().as_millis() as u64;
+  let tls_probe = (url.scheme() == "https").then(|| {
+    TlsProbe::Direct(TlsTarget {
+      host: url.host_str().unwrap_or_default().to_string(),
+      addr: response.remote_addr,
+      alpn: request.client.alpn_protocols(),
+      tls: request.tls.clone(),
+    })
+  });
+  let ms = |duration: Duration| duration.as_millis() as u64;
+  let timing = Timing {
+    dns_ms: dns.map(ms),
+    connect_ms: Some(ms(response.connect)),
+    tls_ms: response.tls_handshake.map(ms),
+    ttfb_ms: ms(response.ttfb),
+    download_ms: ms(response.download),
+  };
+  ReqResponse {
+    status: Some(response.status),
+    headers: response_headers(&response.headers),
+    body: response.body,
+    elapsed_ms,
+    remote_addr: Some(response.remote_addr.to_string()),
+    tls_connection: response.tls,
+    tls_probe,
+    timing: Some(timing),
+    header_map: response.headers,
+    raw_request,
+    ..Default::default()
+  }
+}
+
+/// Sends a request through its proxy with reqwest, which does not expose the connection: only the first byte and
+/// the download are timed, the proxy resolves names and of TLS only the server's certificate is known.
+fn send_proxied(request: PreparedRequest) -> ReqResponse {
+  let started = Instant::now();
+  let raw_request = Some(wire::request(&request));
+  let proxy = request.proxy.describe(&request.url);
+  let client = match request.client.build(&request.proxy, &request.tls) {
+    Ok(client) => client,
+    Err(err) => return ReqResponse { body: err.to_string(), proxy, raw_request, ..Default::default() },
+  };
+  let req_builder = match request.method {
+    HttpMethod::GET => client.get(request.url).headers(request.headers),
+    HttpMethod::POST => client.post(request.url).headers(request.headers).body(request.body),
+    HttpMethod::PUT => client.put(request.url).headers(request.headers).body(request.body),
+    HttpMethod::DELETE => client.delete(request.url).headers(request.headers).body(request.body),
+  };
+
+  let sent_at = Instant::now();
+  let (status, header_map, body, connection) = match req_builder.send() {
+    Ok(response) => {
+      let ttfb = sent_at.elapsed();
+      let status = Some(response.status().as_u16());
+      let header_map = response.headers().clone();
+      let (url, remote_addr) = (response.url().clone(), response.remote_addr());
+      let tls_connection = response
+        .extensions()
+        .get::<reqwest::tls::TlsInfo>()
+        .and_then(|info| tls::describe_der(info.peer_certificate()?).ok())
+        .map(|certificate| ConnectionTls { certificate, alpn: None, verified: !request.tls.insecure });
+      let download_started = Instant::now();
+      let body = match response.text() {
+        Ok(text) => text,
+        Err(err) => err.to_string(),
+      };
+      (status, header_map, body, Some((url, remote_addr, tls_connection, ttfb, download_started.elapsed())))
+    },
+    Err(err) => (None, HeaderMap::new(), err.to_string(), None),
+  };
+  let elapsed_ms = started.elapsed().as_millis() as u64;
+  let headers = response_headers(&header_map);
+  let (remote_addr, tls_connection, tls_probe, timing) = match connection {
+    Some((url, remote_addr, tls_connection, ttfb, download)) => {
+      let tls_probe = (url.scheme() == "https").then_some(TlsProbe::Proxied);
+      let ms = |duration: Duration| duration.as_millis() as u64;
+      let timing = Timing { ttfb_ms: ms(ttfb), download_ms: ms(download), ..Default::default() };
+      (remote_addr.map(|addr| addr.to_string()), tls_connection, tls_probe, Some(timing))
+    },
+    None => (None, None, None, None),
+  };
+  ReqResponse {
+    status,
+    headers,
+    body,
+    elapsed_ms,
+    proxy,
+    remote_addr,
+    tls_connection,
+    tls_probe,
+    timing,
+    header_map,
+    raw_request,
+    ..Default::default()
+  }
+}
+
+/// Sends the request over the Unix socket at `socket`, the rest of the url being the path and query.
+fn send_unix(socket: &str, request: &PreparedRequest) -> ReqResponse {
+  let started = Instant::now();
+  let raw_request = Some(wire::request(request));
+  let target = match unix_target(socket, &request.url) {
+    Ok(target) => target,
+    Err(err) => return ReqResponse { body: err.to_string(), raw_request, ..Default::default() },
+  };
+  let method = request.method.to_string();
+  let response = unix::send(unix::UnixRequest {
+    socket,
+    method: &method,
+    target: &target,
+    headers: &request.headers,
+    body: request.sent_body(),
+    timeout: request.client.timeout_ms.map(Duration::from_millis),
+  });
+  let elapsed_ms = started.elapsed().as_millis() as u64;
+  let response = match response {
+    Ok(response) => response,
+    Err(err) => return ReqResponse { body: err.to_string(), elapsed_ms, raw_request, ..Default::default() },
+  };
+  let ms = |duration: Duration| duration.as_millis() as u64;
+  let timing = Timing {
+    connect_ms: Some(ms(response.connect)),
+    ttfb_ms: ms(response.ttfb),
+    download_ms: ms(response.download),
+    ..Default::default()
+  };
+  ReqResponse {
+    status: Some(response.status),
+    headers: response_headers(&response.headers),
+    body: response.body,
+    elapsed_ms,
+    remote_addr: Some(socket.to_string()),
+    timing: Some(timing),
+    header_map: response.headers,
+    raw_request,
+    ..Default::default()
+  }
+}
+
+/// The path and query of `url`, a url on the Unix socket `socket`.
+fn unix_target(socket: &str, url: &str) -> Result<String, Error> {
+  let prefix = format!("{}{}", unix::SCHEME, socket);
+  let target = url
+    .strip_prefix(&prefix)
+    .ok_or_else(|| format!("{} is not on the Unix socket {}", url, socket))?
+    .trim_end_matches('?');
+  if target.starts_with('/') {
+    Ok(target.to_string())
+  } else {
+    Ok(format!("/{}", target))
+  }
+}
+
+fn response_headers(header_map: &HeaderMap) -> String {
+  format!("{:?}", header_map).replace("\",", "\n").replace('{', " ").replace('}', "")
+}
+
+/// Resolves the host of `url`, returning it with its addresses and how long it took, which is `None` when the host
+/// is mapped. `None` for IP hosts, or when resolution fails, which is left to the caller to report.
+fn resolve(url: &url::Url, mappings: &[HostMapping]) -> Option<(String, Vec<SocketAddr>, Option<Duration>)> {
+  let host = match url.host()? {
+    url::Host::Domain(host) => host.to_string(),
+    _ => return None,
+  };
+  let port = url.port_or_known_default()?;
+  if let Some(mapping) = mappings.iter().find(|mapping| mapping.matches(&host, port)) {
+    return Some((host, vec![SocketAddr::new(mapping.address, port)], None));
+  }
+  let started = Instant::now();
+  let addrs: Vec<SocketAddr> = (host.as_str(), port).to_socket_addrs().ok()?.collect();
+  Some((host, addrs, Some(started.elapsed())))
+}
+
+/// One `name: value` line per header.
+pub fn format_headers(headers: &HeaderMap) -> String {
+  headers
+    .iter()
+    .map(|(name, value)| format!("{}: {}", name, String::from_utf8_lossy(value.as_bytes())))
+    .collect::<Vec<_>>()
+    .join("\n")
+}
+
+pub fn parse_headers(headers: &str) -> Result<HeaderMap, Error> {
+  let mut header_map = HeaderMap::new();
+  for line in headers.lines().filter(|line| !line.trim().is_empty()) {
+    let invalid = |reason: &str| AppError::InvalidHeader { line: line.to_string(), reason: reason.to_string() };
+    let (name, value) = line.split_once(':').ok_or_else(|| invalid("expected `Name: value`"))?;
+    let header_name = HeaderName::from_bytes(name.trim().as_bytes()).map_err(|_| invalid("invalid name"))?;
+    let header_value = HeaderValue::from_str(value.trim()).map_err(|_| invalid("invalid value"))?;
+    header_map.insert(header_name, header_value);
+  }
+  Ok(header_map)
+}
+
+/// Joins `name=value` lines into a query string.
+pub fn parse_query(query: &str) -> Result<String, Error> {
+  let mut pairs = Vec::new();
+  for line in query.lines().filter(|line| !line.is_empty()) {
+    match line.split_once('=') {
+      Some((name, value)) if !name.is_empty() && !name.contains(char::is_whitespace) && !value.is_empty() => {
+        pairs.push(line)
+      },
+      _ => return Err(AppError::InvalidQuery(line.to_string()).into()),
+    }
+  }
+  Ok(pairs.join("&"))
+}
+
+/// The names of the `{{var}}` placeholders in `input` that `values` has no value for.
+pub fn unresolved_variables(input: &str, values: &HashMap<String, String>) -> Vec<String> {
+  PLACEHOLDER
+    .captures_iter(input)
+    .filter_map(|capture| Some(capture.ok()?.get(1)?.as_str().to_string()))
+    .filter(|name| !values.contains_key(name))
+    .collect()
+}
+
+pub fn replace_env_variables(input: &str, values: &HashMap<String, String>) -> String {
+  let mut output = input.to_string();
+
+  for capture in PLACEHOLDER.captures_iter(input) {
+    let capture_matches = capture.unwrap();
+    let key = capture_matches.get(1).unwrap().as_str();
+    let value = match values.get(key) {
+      Some(v) => v.to_string(),
+      None => String::new(),
+    };
+    output = output.replace(capture_matches.get(0).unwrap().as_str(), &value);
+  }
+
+  output
+}
@@ -0,0 +1,130 @@
+use std::time::Duration;
+
+use reqwest::{
+  blocking::{Client, ClientBuilder},
+  redirect::Policy,
+  Proxy,
+};
+use serde::{Deserialize, Serialize};
+
+use super::{proxy::ProxySettings, tls::TlsSettings, Error};
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum HttpVersion {
+  /// HTTP/1.1 or HTTP/2, as negotiated with the server
+  Auto,
+  Http1Only,
+  /// Speaks HTTP/2 from the start, without negotiation
+  Http2PriorKnowledge,
+}
+
+/// Options of the HTTP client. Every field is optional: a request's settings fall back to the global ones from the
+/// config, and those to the reqwest defaults.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct ClientSettings {
+  #[serde(default)]
+  pub connect_timeout_ms: Option<u64>,
+  /// Whole request, from connecting until the body is read
+  #[serde(default)]
+  pub timeout_ms: Option<u64>,
+  /// `0` stops at the first redirect, 10 are followed when unset
+  #[serde(default)]
+  pub max_redirects: Option<usize>,
+  #[serde(default)]
+  pub http_version: Option<HttpVersion>,
+  #[serde(default)]
+  pub gzip: Option<bool>,
+  #[serde(default)]
+  pub brotli: Option<bool>,
+  #[serde(default)]
+  pub deflate: Option<bool>,
+  #[serde(default)]
+  pub user_agent: Option<String>,
+}
+
+impl ClientSettings {
+  /// These settings, with every unset field taken from `defaults`.
+  pub fn or(&self, defaults: &ClientSettings) -> ClientSettings {
+    ClientSettings {
+      connect_timeout_ms: self.connect_timeout_ms.or(defaults.connect_timeout_ms),
+      timeout_ms: self.timeout_ms.or(defaults.timeout_ms),
+      max_redirects: self.max_redirects.or(defaults.max_redirects),
+      http_version: self.http_version.or(defaults.http_version),
+      gzip: self.gzip.or(defaults.gzip),
+      brotli: self.brotli.or(defaults.brotli),
+      deflate: self.deflate.or(defaults.deflate),
+      user_agent: self.user_agent.clone().or_else(|| defaults.user_agent.clone()),
+    }
+  }
+
+  /// A client with these settings, going through the proxies of `proxy` and trusting the certificates of `tls`, both
+  /// already resolved.
+  pub fn build(&self, proxy: &ProxySettings, tls: &TlsSettings) -> Result<Client, Error> {
+    Ok(self.builder(proxy, tls)?.build()?)
+  }
+
+  /// [`ClientSettings::build`], leaving the builder open for more options.
+  pub fn builder(&self, proxy: &ProxySettings, tls: &TlsSettings) -> Result<ClientBuilder, Error> {
+    proxy.validate()?;
+    let proxy = proxy.clone();
+    // the OS proxies are part of the resolved settings, so reqwest must not add its own
+    let mut builder = Client::builder()
+      .no_proxy()
+      .proxy(Proxy::custom(move |url| proxy.proxy_for(url)))
+      .gzip(self.gzip.unwrap_or(true))
+      .brotli(self.brotli.unwrap_or(true))
+      .deflate(self.deflate.unwrap_or(true))
+      .tls_info(true);
+    if let Some(ms) = self.connect_timeout_ms {
+      builder = builder.connect_timeout(Duration::from_millis(ms));
+    }
+    if let Some(ms) = self.timeout_ms {
+      builder = builder.timeout(Duration::from_millis(ms));
+    }
+    // redirects are followed one hop at a time, so each of them can be recorded
+    builder = builder.redirect(Policy::none());
+    builder = match self.http_version {
+      Some(HttpVersion::Http1Only) => builder.http1_only(),
+      Some(HttpVersion::Http2PriorKnowledge) => builder.http2_prior_knowledge(),
+      Some(HttpVersion::Auto) | None => builder,
+    };
+    if let Some(user_agent) = &self.user_agent {
+      builder = builder.user_agent(user_agent);
+    }
+    tls.apply(builder)
+  }
+
+  /// The ALPN protocols the client offers, as the HTTP version preference allows.
+  pub fn alpn_protocols(&self) -> Vec<&'static str> {
+    match self.http_version {
+      Some(HttpVersion::Http1Only) => vec!["http/1.1"],
+      Some(HttpVersion::Http2PriorKnowledge) => vec!["h2"],
+      Some(HttpVersion::Auto) | None => vec!["h2", "http/1.1"],
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_request_settings_override_global_ones() {
+    let global = ClientSettings {
+      timeout_ms: Some(5000),
+      user_agent: Some(String::from("cartero")),
+      gzip: Some(false),
+      ..Default::default()
+    };
+    let request = ClientSettings { timeout_ms: Some(100), max_redirects: Some(0), ..Default::default() };
+    let settings = request.or(&global);
+    assert_eq!(settings.timeout_ms, Some(100));
+    assert_eq!(settings.max_redirects, Some(0));
+    assert_eq!(settings.user_agent.as_deref(), Some("cartero"));
+    assert_eq!(settings.gzip, Some(false));
+    assert!(settings.build(&ProxySettings::default(), &TlsSettings::default()).is_ok());
+  }
+}
@@ -0,0 +1,238 @@
+use std::{
+  future::Future,
+  io::{Read, Write},
+  net::SocketAddr,
+  time::{Duration, Instant},
+};
+
+use flate2::read::{GzDecoder, ZlibDecoder};
+use hyper::{client::conn, Body, Method, Request, Version};
+use reqwest::header::{
+  HeaderMap, HeaderValue, ACCEPT, ACCEPT_ENCODING, CONTENT_ENCODING, CONTENT_LENGTH, HOST, USER_AGENT,
+};
+use tokio::{
+  io::{AsyncRead, AsyncWrite},
+  net::TcpStream,
+  time::timeout,
+};
+use url::{Position, Url};
+
+use super::{
+  client::{ClientSettings, HttpVersion},
+  tls::{self, ConnectionTls},
+  Error, HttpMethod, PreparedRequest,
+};
+
+/// A response to a request sent over a connection opened here rather than by reqwest, so connecting and the TLS
+/// handshake can be timed on the connection the request used.
+pub struct DirectResponse {
+  pub status: u16,
+  pub headers: HeaderMap,
+  pub body: String,
+  pub remote_addr: SocketAddr,
+  pub connect: Duration,
+  /// `None` over plain HTTP
+  pub tls_handshake: Option<Duration>,
+  /// `None` over plain HTTP
+  pub tls: Option<ConnectionTls>,
+  pub ttfb: Duration,
+  pub download: Duration,
+}
+
+/// Anything hyper can speak HTTP over, so plain and TLS connections are handled alike.
+trait Io: AsyncRead + AsyncWrite + Unpin + Send {}
+
+impl<T: AsyncRead + AsyncWrite + Unpin + Send> Io for T {}
+
+/// Sends `request` to `url` over a new connection to the first of `addrs` that accepts it, with the request's client
+/// settings and TLS settings, the way reqwest would send it without a proxy. Blocking.
+pub fn send(request: &PreparedRequest, url: &Url, addrs: &[SocketAddr]) -> Result<DirectResponse, Error> {
+  let runtime = tokio::runtime::Builder::new_current_thread().enable_all().build()?;
+  runtime.block_on(within(request.client.timeout_ms, "request", exchange(request, url, addrs)))
+}
+
+/// Headers a client adds on its own unless the request sets them: `accept`, `user-agent` when configured, the
+/// enabled encodings as `accept-encoding` and `content-length` for methods with a body.
+pub fn default_headers(request: &PreparedRequest) -> HeaderMap {
+  let mut headers = HeaderMap::new();
+  let client = &request.client;
+  if !request.headers.contains_key(ACCEPT) {
+    headers.insert(ACCEPT, HeaderValue::from_static("*/*"));
+  }
+  if let Some(user_agent) = client.user_agent.as_ref().filter(|_| !request.headers.contains_key(USER_AGENT)) {
+    if let Ok(value) = HeaderValue::from_str(user_agent) {
+      headers.insert(USER_AGENT, value);
+    }
+  }
+  let encodings: Vec<&str> = [(client.gzip, "gzip"), (client.brotli, "br"), (client.deflate, "deflate")]
+    .into_iter()
+    .filter(|(enabled, _)| enabled.unwrap_or(true))
+    .map(|(_, encoding)| encoding)
+    .collect();
+  if !encodings.is_empty() && !request.headers.contains_key(ACCEPT_ENCODING) {
+    if let Ok(value) = HeaderValue::from_str(&encodings.join(", ")) {
+      headers.insert(ACCEPT_ENCODING, value);
+    }
+  }
+  if request.method != HttpMethod::GET {
+    headers.insert(CONTENT_LENGTH, HeaderValue::from(request.sent_body().len()));
+  }
+  headers
+}
+
+async fn exchange(request: &PreparedRequest, url: &Url, addrs: &[SocketAddr]) -> Result<DirectResponse, Error> {
+  let host = url.host_str().ok_or_else(|| format!("{} has no host", url))?;
+  let connecting = async {
+    let started = Instant::now();
+    let (stream, remote_addr) = connect(addrs).await?;
+    let connect = started.elapsed();
+    if url.scheme() != "https" {
+      return Ok::<_, Error>((Box::new(stream) as Box<dyn Io>, remote_addr, connect, None, None));
+    }
+    let connector = request.tls.connector(&request.client.alpn_protocols())?;
+    let handshake_started = Instant::now();
+    let domain = host.trim_start_matches('[').trim_end_matches(']');
+    let stream = tokio_native_tls::TlsConnector::from(connector).connect(domain, stream).await?;
+    let tls_handshake = handshake_started.elapsed();
+    let tls = describe_connection(stream.get_ref(), !request.tls.insecure)?;
+    Ok((Box::new(stream) as Box<dyn Io>, remote_addr, connect, Some(tls_handshake), Some(tls)))
+  };
+  let (stream, remote_addr, connect, tls_handshake, tls) =
+    within(request.client.connect_timeout_ms, "connect", connecting)
+      .await
+      .map_err(|e| format!("error trying to connect: {}", e))?;
+
+  let h2 = match request.client.http_version {
+    Some(HttpVersion::Http2PriorKnowledge) => true,
+    Some(HttpVersion::Http1Only) => false,
+    Some(HttpVersion::Auto) | None => tls.as_ref().and_then(|tls| tls.alpn.as_deref()) == Some("h2"),
+  };
+  let (mut sender, connection) = conn::Builder::new().http2_only(h2).handshake::<_, Body>(stream).await?;
+  // errors of the connection surface through the request
+  tokio::spawn(connection);
+
+  let sent_at = Instant::now();
+  let response = sender.send_request(http_request(request, url, h2)?).await?;
+  let ttfb = sent_at.elapsed();
+  let (parts, body) = response.into_parts();
+  let download_started = Instant::now();
+  let body = hyper::body::to_bytes(body).await?;
+  let download = download_started.elapsed();
+  let mut headers = parts.headers;
+  let body = decode(&mut headers, body.to_vec(), &request.client)?;
+  Ok(DirectResponse {
+    status: parts.status.as_u16(),
+    headers,
+    body: String::from_utf8_lossy(&body).to_string(),
+    remote_addr,
+    connect,
+    tls_handshake,
+    tls,
+    ttfb,
+    download,
+  })
+}
+
+/// The certificate and ALPN protocol of `stream`, the request's own TLS connection.
+fn describe_connection(
+  stream: &native_tls::TlsStream<impl Read + Write>,
+  verified: bool,
+) -> Result<ConnectionTls, Error> {
+  let certificate = stream.peer_certificate()?.ok_or("The server sent no certificate")?;
+  let alpn = stream.negotiated_alpn()?.map(|alpn| String::from_utf8_lossy(&alpn).to_string());
+  Ok(ConnectionTls { certificate: tls::describe_der(&certificate.to_der()?)?, alpn, verified })
+}
+
+/// Connects to the first of `addrs` that accepts, in order.
+async fn connect(addrs: &[SocketAddr]) -> Result<(TcpStream, SocketAddr), Error> {
+  let mut last_error = None;
+  for addr in addrs {
+    match TcpStream::connect(addr).await {
+      Ok(stream) => return Ok((stream, *addr)),
+      Err(e) => last_error = Some(e),
+    }
+  }
+  Err(last_error.map(Error::from).unwrap_or_else(|| "no address to connect to".into()))
+}
+
+/// Runs `future`, failing with `{what} timed out` after `timeout_ms`.
+async fn within<T>(
+  timeout_ms: Option<u64>,
+  what: &str,
+  future: impl Future<Output = Result<T, Error>>,
+) -> Result<T, Error> {
+  match timeout_ms {
+    Some(ms) => timeout(Duration::from_millis(ms), future).await.map_err(|_| format!("{} timed out", what))?,
+    None => future.await,
+  }
+}
+
+/// The request with the headers reqwest would add. HTTP/1.1 takes the path and a `host` header, HTTP/2 the whole url.
+fn http_request(request: &PreparedRequest, url: &Url, h2: bool) -> Result<Request<Body>, Error> {
+  let mut headers = request.headers.clone();
+  if !h2 && !headers.contains_key(HOST) {
+    headers.insert(HOST, HeaderValue::from_str(&url[Position::BeforeHost..Position::AfterPort])?);
+  }
+  headers.extend(default_headers(request));
+  let (target, version) = match h2 {
+    true => (url.as_str(), Version::HTTP_2),
+    false => (&url[Position::BeforePath..Position::AfterQuery], Version::HTTP_11),
+  };
+  let mut http_request = Request::builder()
+    .method(Method::from_bytes(request.method.to_string().as_bytes())?)
+    .uri(target)
+    .version(version)
+    .body(Body::from(request.sent_body().to_string()))?;
+  *http_request.headers_mut() = headers;
+  Ok(http_request)
+}
+
+/// Decompresses `body` when it is in an enabled encoding, dropping `content-encoding` and `content-length` as they
+/// no longer apply, like reqwest does.
+fn decode(headers: &mut HeaderMap, body: Vec<u8>, client: &ClientSettings) -> Result<Vec<u8>, Error> {
+  let encoding = headers.get(CONTENT_ENCODING).and_then(|v| v.to_str().ok()).map(|v| v.trim().to_ascii_lowercase());
+  let mut decoded = Vec::new();
+  match encoding.as_deref() {
+    _ if body.is_empty() => return Ok(body),
+    Some("gzip") if client.gzip.unwrap_or(true) => GzDecoder::new(body.as_slice()).read_to_end(&mut decoded)?,
+    Some("br") if client.brotli.unwrap_or(true) => {
+      brotli_decompressor::Decompressor::new(body.as_slice(), 4096).read_to_end(&mut decoded)?
+    },
+    Some("deflate") if client.deflate.unwrap_or(true) => ZlibDecoder::new(body.as_slice()).read_to_end(&mut decoded)?,
+    _ => return Ok(body),
+  };
+  headers.remove(CONTENT_ENCODING);
+  headers.remove(CONTENT_LENGTH);
+  Ok(decoded)
+}
+
+#[cfg(test)]
+mod tests {
+  use flate2::{write::GzEncoder, Compression};
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_decode_follows_the_enabled_encodings() {
+    let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(b"{\"id\":1}").unwrap();
+    let gzipped = encoder.finish().unwrap();
+    let gzip_headers = || {
+      let mut headers = HeaderMap::new();
+      headers.insert(CONTENT_ENCODING, HeaderValue::from_static("gzip"));
+      headers.insert(CONTENT_LENGTH, HeaderValue::from(gzipped.len()));
+      headers
+    };
+
+    let mut headers = gzip_headers();
+    let body = decode(&mut headers, gzipped.clone(), &ClientSettings::default()).unwrap();
+    assert_eq!(body, b"{\"id\":1}");
+    assert!(headers.is_empty());
+
+    let mut headers = gzip_headers();
+    let disabled = ClientSettings { gzip: Some(false), ..Default::default() };
+    assert_eq!(decode(&mut headers, gzipped.clone(), &disabled).unwrap(), gzipped);
+    assert_eq!(headers, gzip_headers());
+  }
+}
@@ -0,0 +1,121 @@
+use chrono::Utc;
+use reqwest::header::{COOKIE, SET_COOKIE};
+
+use super::{
+  add_cookies, format_headers,
+  redirect::{self, Redirect},
+  send, Context, PreparedRequest, ReqResponse,
+};
+use crate::{auth::Secrets, repository::local_storage::RequestInput};
+
+/// A request as it went over the wire, together with what came back.
+#[derive(Clone)]
+pub struct Sent {
+  pub request: RequestInput,
+  pub url: String,
+  /// Headers as they were sent, after auth was applied
+  pub headers: String,
+  pub response: ReqResponse,
+  /// Where the auth put credentials, to leave them out of the history
+  pub secrets: Secrets,
+}
+
+/// Sends `prepared`, following redirects one hop at a time up to the request's limit. Every hop is recorded on the
+/// final response, and its cookies are stored before the next hop is sent.
+pub fn dispatch(request: &RequestInput, context: &mut Context, prepared: PreparedRequest) -> Sent {
+  let url = prepared.url.clone();
+  let headers = format_headers(&prepared.headers);
+  let max_redirects = prepared.client.max_redirects.unwrap_or(redirect::DEFAULT_MAX_REDIRECTS);
+  let mut redirects = Vec::new();
+  let mut hop = prepared;
+  let mut response = loop {
+    let response = send(hop.clone());
+    if request.cookies {
+      store_cookies(context, &hop.url, &response);
+    }
+    let next = match redirect::follow(&hop, &response) {
+      Some(next) if redirects.len() < max_redirects => next,
+      _ => break response,
+    };
+    let (location, mut next) = next;
+    if request.cookies {
+      // the jar may have changed, and the next hop may be on another site
+      next.headers.remove(COOKIE);
+      let _ = add_cookies(&mut next.headers, &next.url, &context.cookie_jar);
+    }
+    redirects.push(Redirect { method: hop.method, url: hop.url, location, response });
+    hop = next;
+  };
+  response.redirects = redirects;
+  let secrets = context.auth_for(request).map(|auth| auth.secrets(&context.env)).unwrap_or_default();
+  let request = RequestInput { server: context.server.clone(), ..request.clone() };
+  Sent { request, url, headers, response, secrets }
+}
+
+fn store_cookies(context: &mut Context, url: &str, response: &ReqResponse) {
+  if let Ok(parsed) = url::Url::parse(url) {
+    let set_cookies = response.header_map.get_all(SET_COOKIE).iter().filter_map(|v| v.to_str().ok());
+    context.cookie_jar.store_response(&parsed, set_cookies, Utc::now());
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::http::{
+    prepare,
+    test_server::{json_response, serve},
+  };
+
+  /// Prepares `request` against `context` and dispatches it.
+  fn dispatch_input(request: &RequestInput, context: &mut Context) -> Sent {
+    let prepared = prepare(request, context).unwrap();
+    dispatch(request, context, prepared)
+  }
+
+  #[test]
+  fn test_cookies_from_a_login_are_sent_back() {
+    let login = "HTTP/1.1 200 OK\r\nSet-Cookie: sid=abc; Path=/; HttpOnly\r\n\
+                 Content-Length: 0\r\nConnection: close\r\n\r\n";
+    let (base_url, server) =
+      serve(vec![login.to_string(), json_response("200 OK", "{}"), json_response("200 OK", "{}")]);
+    let mut context = Context { server: base_url, ..Default::default() };
+    let request = |path: &str, cookies: bool| RequestInput { path: path.to_string(), cookies, ..Default::default() };
+
+    dispatch_input(&request("/login", true), &mut context);
+    dispatch_input(&request("/admin", true), &mut context);
+    dispatch_input(&request("/admin", false), &mut context);
+
+    let requests = server.join().unwrap();
+    assert!(requests[1].to_lowercase().contains("cookie: sid=abc"));
+    assert!(!requests[2].to_lowercase().contains("cookie:"));
+  }
+
+  #[test]
+  fn test_redirects_are_recorded() {
+    let redirect = "HTTP/1.1 302 Found\r\nLocation: /dashboard\r\nSet-Cookie: sid=abc; Path=/\r\n\
+                    Content-Length: 5\r\nConnection: close\r\n\r\nmoved";
+    let (base_url, server) = serve(vec![redirect.to_string(), json_response("200 OK", "{}"), redirect.to_string()]);
+    let mut context = Context { server: base_url.clone(), ..Default::default() };
+    let mut request = RequestInput { path: String::from("/login"), cookies: true, ..Default::default() };
+
+    let sent = dispatch_input(&request, &mut context);
+    let response = &sent.response;
+    assert_eq!(response.status, Some(200));
+    assert_eq!(response.redirects.len(), 1);
+    let hop = &response.redirects[0];
+    assert_eq!((hop.url.as_str(), hop.location.as_str()), (format!("{}/login?", base_url).as_str(), "/dashboard"));
+    assert_eq!((hop.response.status, hop.response.body.as_str()), (Some(302), "moved"));
+
+    request.client.max_redirects = Some(0);
+    let sent = dispatch_input(&request, &mut context);
+    assert_eq!(sent.response.status, Some(302));
+    assert!(sent.response.redirects.is_empty());
+
+    let requests = server.join().unwrap();
+    assert!(requests[1].starts_with("GET /dashboard"));
+    assert!(requests[1].to_lowercase().contains("cookie: sid=abc"));
+  }
+}
@@ -0,0 +1,88 @@
+use std::{collections::HashMap, net::IpAddr};
+
+use super::{replace_env_variables, Error};
+
+/// A `host:port -> address` mapping, like curl's `--resolve`. Requests to `host` connect to `address` instead, with
+/// the Host header and TLS SNI left as they are. Without a port the mapping applies to every port.
+#[derive(Clone, Debug, PartialEq)]
+pub struct HostMapping {
+  pub host: String,
+  pub port: Option<u16>,
+  pub address: IpAddr,
+}
+
+impl HostMapping {
+  pub fn parse(mapping: &str) -> Result<HostMapping, Error> {
+    let invalid = || format!("Invalid host mapping, expected `host:port -> address`: {}", mapping);
+    let (target, address) = mapping.split_once("->").ok_or_else(invalid)?;
+    let address = address.trim().trim_start_matches('[').trim_end_matches(']');
+    let address = address.parse::<IpAddr>().map_err(|_| invalid())?;
+    let target = target.trim();
+    let (host, port) = match target.rsplit_once(':') {
+      Some((host, port)) => (host, Some(port.parse::<u16>().map_err(|_| invalid())?)),
+      None => (target, None),
+    };
+    if host.is_empty() {
+      return Err(invalid().into());
+    }
+    Ok(HostMapping { host: host.to_lowercase(), port, address })
+  }
+
+  pub fn matches(&self, host: &str, port: u16) -> bool {
+    self.host.eq_ignore_ascii_case(host) && self.port.map(|p| p == port).unwrap_or(true)
+  }
+}
+
+/// Parses the mappings of an environment, with env variables replaced.
+pub fn parse_mappings(mappings: &[String], env: &HashMap<String, String>) -> Result<Vec<HostMapping>, Error> {
+  mappings.iter().map(|mapping| HostMapping::parse(&replace_env_variables(mapping, env))).collect()
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::http::{
+    self,
+    proxy::ProxySettings,
+    test_server::{json_response, serve},
+    PreparedRequest,
+  };
+
+  #[test]
+  fn test_parse_host_mapping() {
+    let mapping = HostMapping::parse("API.example.com:443 -> 127.0.0.1").unwrap();
+    let host = String::from("api.example.com");
+    assert_eq!(mapping, HostMapping { host, port: Some(443), address: [127, 0, 0, 1].into() });
+    assert!(mapping.matches("api.example.com", 443));
+    assert!(!mapping.matches("api.example.com", 80));
+
+    let any_port = HostMapping::parse("api.example.com->[::1]").unwrap();
+    assert!(any_port.matches("API.EXAMPLE.COM", 8080));
+
+    assert!(HostMapping::parse("api.example.com:443 127.0.0.1").is_err());
+    assert!(HostMapping::parse("api.example.com:https -> 127.0.0.1").is_err());
+    assert!(HostMapping::parse("api.example.com -> localhost").is_err());
+  }
+
+  #[test]
+  fn test_mapped_host_keeps_its_host_header() {
+    let (base_url, server) = serve(vec![json_response("200 OK", "{}")]);
+    let port = base_url.rsplit(':').next().unwrap();
+    let env = HashMap::from([(String::from("local"), String::from("127.0.0.1"))]);
+    let mappings = vec![format!("api.example.test:{} -> {{{{local}}}}", port)];
+    let request = PreparedRequest {
+      url: format!("http://api.example.test:{}/users", port),
+      proxy: ProxySettings { system: false, ..Default::default() },
+      resolve: parse_mappings(&mappings, &env).unwrap(),
+      ..Default::default()
+    };
+    let response = http::send(request);
+    let requests = server.join().unwrap();
+
+    assert_eq!(response.status, Some(200));
+    assert!(requests[0].to_lowercase().contains(&format!("host: api.example.test:{}", port)));
+    assert_eq!(response.timing.unwrap().dns_ms, None);
+  }
+}
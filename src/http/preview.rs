@@ -0,0 +1,198 @@
+use std::collections::HashMap;
+
+use reqwest::header::HeaderMap;
+
+use super::{format_headers, parse_headers, parse_query, substitute, Context, PLACEHOLDER};
+use crate::{auth::Auth, components::home::server::HttpMethod, repository::local_storage::RequestInput};
+
+/// A piece of a resolved line: text, or a `{{var}}` the env has no value for.
+#[derive(Clone, Debug, PartialEq)]
+pub enum Segment {
+  Text(String),
+  Unresolved(String),
+}
+
+/// A line of the request with its env variables substituted, and why it cannot be sent when it cannot.
+#[derive(Clone, Debug, PartialEq)]
+pub struct PreviewLine {
+  pub segments: Vec<Segment>,
+  pub error: Option<String>,
+}
+
+impl PreviewLine {
+  fn new(line: &str, env: &HashMap<String, String>) -> PreviewLine {
+    PreviewLine { segments: resolve(line, env), error: None }
+  }
+
+  /// The line as it is sent, with unresolved variables left empty.
+  pub fn resolved(&self) -> String {
+    self
+      .segments
+      .iter()
+      .filter_map(|segment| match segment {
+        Segment::Text(text) => Some(text.as_str()),
+        Segment::Unresolved(_) => None,
+      })
+      .collect()
+  }
+
+  pub fn unresolved(&self) -> impl Iterator<Item = &str> {
+    self.segments.iter().filter_map(|segment| match segment {
+      Segment::Unresolved(name) => Some(name.as_str()),
+      Segment::Text(_) => None,
+    })
+  }
+}
+
+/// The request as it would be sent with the current env, without sending it.
+#[derive(Clone, Debug)]
+pub struct Preview {
+  pub method: HttpMethod,
+  /// The final url with its query percent-encoded, or why it could not be built
+  pub url: Result<String, String>,
+  pub query: Vec<PreviewLine>,
+  pub headers: Vec<PreviewLine>,
+  /// Headers the auth and the cookie jar add, or the query parameter of a query API key
+  pub added_headers: Vec<String>,
+  /// Set for auth types whose headers are only computed when sending, as they depend on the time or a challenge
+  pub signed_on_send: Option<&'static str>,
+  pub body: Vec<PreviewLine>,
+}
+
+impl Preview {
+  /// Unresolved variables and invalid lines, for the summary.
+  pub fn problems(&self) -> Vec<String> {
+    let lines = self.query.iter().chain(&self.headers).chain(&self.body);
+    let mut unresolved: Vec<&str> = lines.clone().flat_map(PreviewLine::unresolved).collect();
+    unresolved.sort();
+    unresolved.dedup();
+    let mut problems: Vec<String> =
+      unresolved.iter().map(|name| format!("{{{{{}}}}} is not in the env", name)).collect();
+    problems.extend(lines.filter_map(|line| line.error.clone()));
+    if let Err(err) = &self.url {
+      problems.push(err.clone());
+    }
+    problems
+  }
+}
+
+/// Resolves `request` against `context` the way [`super::prepare`] does, but with unresolved variables left empty and
+/// each query, header and body line kept apart, so problems can be pointed at.
+pub fn preview(request: &RequestInput, context: &Context) -> Preview {
+  let env = &context.env;
+  let lines = |text: &str| -> Vec<PreviewLine> {
+    text.lines().filter(|line| !line.trim().is_empty()).map(|line| PreviewLine::new(line, env)).collect()
+  };
+  let query = lines(&request.query)
+    .into_iter()
+    .map(|mut line| {
+      line.error = parse_query(&line.resolved()).err().map(|e| e.to_string());
+      line
+    })
+    .collect();
+  let headers = lines(&request.headers)
+    .into_iter()
+    .map(|mut line| {
+      line.error = parse_headers(&line.resolved()).err().map(|e| e.to_string());
+      line
+    })
+    .collect();
+  let body = request.payload.lines().map(|line| PreviewLine::new(line, env)).collect();
+
+  let (url, added_headers) = match substitute(request, context) {
+    Ok(prepared) => {
+      let own = parse_headers(&super::replace_env_variables(&request.headers, env)).unwrap_or_default();
+      let added = added_headers(&own, &prepared.headers);
+      let url = url::Url::parse(&prepared.url).map(|url| url.to_string()).map_err(|e| format!("Invalid url: {}", e));
+      (url, added)
+    },
+    Err(err) => (Err(err.to_string()), Vec::new()),
+  };
+  let signed_on_send = match context.auth_for(request) {
+    Some(Auth::AwsSigV4(_)) => Some("AWS SigV4 signature"),
+    Some(Auth::Hmac(_)) => Some("HMAC signature"),
+    Some(Auth::Digest(_)) => Some("Digest response"),
+    _ => None,
+  };
+  Preview { method: request.method, url, query, headers, added_headers, signed_on_send, body }
+}
+
+/// `name: value` lines of `prepared` that `own` does not have as they are.
+fn added_headers(own: &HeaderMap, prepared: &HeaderMap) -> Vec<String> {
+  let mut added = HeaderMap::new();
+  for (name, value) in prepared {
+    if !own.get_all(name).iter().any(|own_value| own_value == value) {
+      added.append(name, value.clone());
+    }
+  }
+  let formatted = format_headers(&added);
+  formatted.lines().map(String::from).collect()
+}
+
+/// Splits `line` around its `{{var}}` placeholders, substituting the ones the env has.
+fn resolve(line: &str, env: &HashMap<String, String>) -> Vec<Segment> {
+  let mut segments = Vec::new();
+  let mut text = String::new();
+  let mut last = 0;
+  for capture in PLACEHOLDER.captures_iter(line).filter_map(|capture| capture.ok()) {
+    let (placeholder, name) = (capture.get(0).unwrap(), capture.get(1).unwrap().as_str());
+    text.push_str(&line[last..placeholder.start()]);
+    match env.get(name) {
+      Some(value) => text.push_str(value),
+      None => {
+        if !text.is_empty() {
+          segments.push(Segment::Text(std::mem::take(&mut text)));
+        }
+        segments.push(Segment::Unresolved(name.to_string()));
+      },
+    }
+    last = placeholder.end();
+  }
+  text.push_str(&line[last..]);
+  if !text.is_empty() {
+    segments.push(Segment::Text(text));
+  }
+  segments
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_preview_points_at_problems() {
+    let context = Context {
+      server: String::from("https://api.example.com"),
+      env: HashMap::from([(String::from("token"), String::from("abc")), (String::from("q"), String::from("a b"))]),
+      default_auth: Some(Auth::Bearer { token: String::from("{{token}}") }),
+      ..Default::default()
+    };
+    let request = RequestInput {
+      path: String::from("/search"),
+      query: String::from("q={{q}}\nbroken\nlimit={{limit}}"),
+      headers: String::from("X-Trace: {{trace}}\nAccept: application/json"),
+      ..Default::default()
+    };
+    let preview = preview(&request, &context);
+
+    assert_eq!(preview.query[0].segments, vec![Segment::Text(String::from("q=a b"))]);
+    assert_eq!(preview.query[2].unresolved().collect::<Vec<_>>(), vec!["limit"]);
+    assert!(preview.query[1].error.as_ref().unwrap().starts_with("Invalid query line `broken`"));
+    assert_eq!(
+      preview.headers[0].segments,
+      vec![Segment::Text(String::from("X-Trace: ")), Segment::Unresolved(String::from("trace"))]
+    );
+    // `limit=` has no value once resolved, which makes the whole query invalid
+    assert!(preview.query[2].error.is_some());
+    assert!(preview.url.is_err());
+    assert_eq!(preview.problems().len(), 5);
+
+    let request = RequestInput { query: String::from("q={{q}}"), headers: String::new(), ..request };
+    let preview = super::preview(&request, &context);
+    assert_eq!(preview.url, Ok(String::from("https://api.example.com/search?q=a%20b")));
+    assert_eq!(preview.added_headers, vec![String::from("authorization: Bearer abc")]);
+    assert!(preview.problems().is_empty());
+  }
+}
@@ -0,0 +1,127 @@
+use reqwest::header::{HeaderMap, AUTHORIZATION, CONTENT_LENGTH, CONTENT_TYPE, COOKIE, LOCATION, PROXY_AUTHORIZATION};
+use serde::{Deserialize, Serialize};
+use url::{Position, Url};
+
+use super::{unix, unix_target, PreparedRequest, ReqResponse};
+use crate::components::home::server::HttpMethod;
+
+/// Redirects followed when a request does not set `max_redirects`, as reqwest does.
+pub const DEFAULT_MAX_REDIRECTS: usize = 10;
+
+/// One redirect response of a chain, before the final response.
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct Redirect {
+  pub method: HttpMethod,
+  pub url: String,
+  /// Where the server sent the request, as it appeared in the response
+  pub location: String,
+  pub response: ReqResponse,
+}
+
+/// The request to send after `response` to `request`, or `None` when it is not a redirect. Like browsers, 301, 302
+/// and 303 turn POSTs into GETs without a body, while 307 and 308 resend the same request. Credentials are dropped
+/// when the redirect leaves the origin. Over a Unix socket, the location is resolved against the path and the chain
+/// stops at locations on another host.
+pub fn follow(request: &PreparedRequest, response: &ReqResponse) -> Option<(String, PreparedRequest)> {
+  let status = response.status?;
+  if !matches!(status, 301 | 302 | 303 | 307 | 308) {
+    return None;
+  }
+  let location = response.header_map.get(LOCATION)?.to_str().ok()?.to_string();
+  let mut next = request.clone();
+  let leaves_origin = match &request.unix_socket {
+    Some(socket) => {
+      next.url = unix_location(socket, &request.url, &location)?;
+      false
+    },
+    None => {
+      let from = Url::parse(&request.url).ok()?;
+      let to = from.join(&location).ok()?;
+      next.url = to.to_string();
+      from.origin() != to.origin()
+    },
+  };
+  if status == 303 || (matches!(status, 301 | 302) && request.method == HttpMethod::POST) {
+    next.method = HttpMethod::GET;
+    next.body = String::new();
+    remove(&mut next.headers, &[CONTENT_TYPE, CONTENT_LENGTH]);
+  }
+  if leaves_origin {
+    remove(&mut next.headers, &[AUTHORIZATION, PROXY_AUTHORIZATION, COOKIE]);
+  }
+  Some((location, next))
+}
+
+/// `location` resolved against the path of `url`, a url on the Unix socket `socket`.
+fn unix_location(socket: &str, url: &str, location: &str) -> Option<String> {
+  let base = Url::parse(&format!("http://localhost{}", unix_target(socket, url).ok()?)).ok()?;
+  let to = base.join(location).ok()?;
+  if to.origin() != base.origin() {
+    return None;
+  }
+  Some(format!("{}{}{}", unix::SCHEME, socket, &to[Position::BeforePath..Position::AfterQuery]))
+}
+
+fn remove(headers: &mut HeaderMap, names: &[reqwest::header::HeaderName]) {
+  for name in names {
+    headers.remove(name);
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use reqwest::header::HeaderValue;
+
+  use super::*;
+
+  fn redirect(status: u16, location: &str) -> ReqResponse {
+    let mut header_map = HeaderMap::new();
+    header_map.insert(LOCATION, HeaderValue::from_str(location).unwrap());
+    ReqResponse { status: Some(status), header_map, ..Default::default() }
+  }
+
+  #[test]
+  fn test_follow() {
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer abc"));
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    let login = PreparedRequest {
+      method: HttpMethod::POST,
+      url: String::from("https://app.example.com/login"),
+      headers,
+      body: String::from("{}"),
+      ..Default::default()
+    };
+
+    let (location, next) = follow(&login, &redirect(303, "/home")).unwrap();
+    assert_eq!(location, "/home");
+    assert_eq!(next.url, "https://app.example.com/home");
+    assert_eq!(next.method, HttpMethod::GET);
+    assert_eq!(next.body, "");
+    assert!(next.headers.contains_key(AUTHORIZATION) && !next.headers.contains_key(CONTENT_TYPE));
+
+    let (_, next) = follow(&login, &redirect(307, "https://sso.example.com/auth")).unwrap();
+    assert_eq!(next.method, HttpMethod::POST);
+    assert_eq!(next.body, "{}");
+    assert!(!next.headers.contains_key(AUTHORIZATION));
+
+    assert!(follow(&login, &ReqResponse { status: Some(200), ..Default::default() }).is_none());
+  }
+
+  #[test]
+  fn test_follow_over_a_unix_socket() {
+    let request = PreparedRequest {
+      url: String::from("unix:///var/run/docker.sock/v1.43/containers/json"),
+      unix_socket: Some(String::from("/var/run/docker.sock")),
+      ..Default::default()
+    };
+
+    let (_, next) = follow(&request, &redirect(301, "/v1.44/containers/json?all=1")).unwrap();
+    assert_eq!(next.url, "unix:///var/run/docker.sock/v1.44/containers/json?all=1");
+    let (_, next) = follow(&request, &redirect(302, "images")).unwrap();
+    assert_eq!(next.url, "unix:///var/run/docker.sock/v1.43/containers/images");
+    assert!(follow(&request, &redirect(302, "https://registry.example.com/")).is_none());
+    assert!(unix_target("/var/run/docker.sock", "unix:///tmp/other.sock/info").is_err());
+  }
+}
@@ -0,0 +1,186 @@
+use std::{
+  fs,
+  io::{self, BufRead, BufReader, Read, Write},
+  net::{TcpListener, TcpStream},
+  path::Path,
+  thread::{self, JoinHandle},
+};
+
+use openssl::{
+  asn1::Asn1Time,
+  bn::BigNum,
+  ec::{EcGroup, EcKey},
+  hash::MessageDigest,
+  nid::Nid,
+  pkcs12::Pkcs12,
+  pkey::{PKey, Private},
+  ssl::{SslAcceptor, SslMethod, SslVerifyMode},
+  x509::{
+    extension::{BasicConstraints, ExtendedKeyUsage, SubjectAlternativeName},
+    X509NameBuilder, X509,
+  },
+};
+
+/// Serves the given raw HTTP responses, one per connection, on a random local port. The handle yields the raw
+/// requests that were received, in order.
+pub fn serve(responses: Vec<String>) -> (String, JoinHandle<Vec<String>>) {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let base_url = format!("http://{}", listener.local_addr().unwrap());
+  let handle = thread::spawn(move || {
+    let mut requests = Vec::new();
+    for response in responses {
+      let (mut stream, _) = listener.accept().unwrap();
+      requests.push(exchange(&mut stream, &response));
+    }
+    requests
+  });
+  (base_url, handle)
+}
+
+/// Like [`serve`] over TLS. Failed handshakes are recorded as `handshake failed` and use
+/// up their response.
+pub fn serve_tls(acceptor: SslAcceptor, responses: Vec<String>) -> (String, JoinHandle<Vec<String>>) {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let base_url = format!("https://localhost:{}", listener.local_addr().unwrap().port());
+  let handle = thread::spawn(move || {
+    let mut requests = Vec::new();
+    for response in responses {
+      let (stream, _) = listener.accept().unwrap();
+      match acceptor.accept(stream) {
+        Ok(mut stream) => requests.push(exchange(&mut stream, &response)),
+        Err(_) => requests.push(String::from("handshake failed")),
+      }
+    }
+    requests
+  });
+  (base_url, handle)
+}
+
+/// A SOCKS5 proxy without authentication, tunneling one connection to the local `port` whatever address it is asked
+/// for. The handle yields the requested domain and port once the tunnel is closed.
+pub fn serve_socks(port: u16) -> (String, JoinHandle<String>) {
+  let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+  let proxy_url = format!("socks5h://{}", listener.local_addr().unwrap());
+  let handle = thread::spawn(move || {
+    let (mut client, _) = listener.accept().unwrap();
+    let mut greeting = [0; 2];
+    client.read_exact(&mut greeting).unwrap();
+    client.read_exact(&mut vec![0; greeting[1] as usize]).unwrap();
+    client.write_all(&[5, 0]).unwrap();
+    // version, connect, reserved, then a domain as its length and bytes
+    let mut head = [0; 5];
+    client.read_exact(&mut head).unwrap();
+    let mut domain = vec![0; head[4] as usize];
+    client.read_exact(&mut domain).unwrap();
+    let mut requested_port = [0; 2];
+    client.read_exact(&mut requested_port).unwrap();
+    client.write_all(&[5, 0, 0, 1, 127, 0, 0, 1, 0, 0]).unwrap();
+
+    let mut server = TcpStream::connect(("127.0.0.1", port)).unwrap();
+    let (mut from_client, mut to_server) = (client.try_clone().unwrap(), server.try_clone().unwrap());
+    thread::spawn(move || io::copy(&mut from_client, &mut to_server));
+    let _ = io::copy(&mut server, &mut client);
+    format!("{}:{}", String::from_utf8_lossy(&domain), u16::from_be_bytes(requested_port))
+  });
+  (proxy_url, handle)
+}
+
+/// Reads one request from `stream` and writes `response` back. A client that hangs up early gets an empty request.
+fn exchange(stream: &mut (impl Read + Write), response: &str) -> String {
+  let mut reader = BufReader::new(&mut *stream);
+  let mut request = String::new();
+  let mut content_length = 0;
+  loop {
+    let mut line = String::new();
+    reader.read_line(&mut line).unwrap_or_default();
+    if let Some((name, value)) = line.split_once(':') {
+      if name.eq_ignore_ascii_case("content-length") {
+        content_length = value.trim().parse().unwrap();
+      }
+    }
+    request.push_str(&line);
+    if line == "\r\n" || line.is_empty() {
+      break;
+    }
+  }
+  let mut body = vec![0; content_length];
+  reader.read_exact(&mut body).unwrap_or_default();
+  request.push_str(&String::from_utf8_lossy(&body));
+  drop(reader);
+  let _ = stream.write_all(response.as_bytes());
+  request
+}
+
+/// Builds a `Connection: close` response with a JSON body.
+pub fn json_response(status: &str, body: &str) -> String {
+  format!(
+    "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+    status,
+    body.len(),
+    body
+  )
+}
+
+/// A private CA with a `localhost` server certificate and a client certificate, written as PEM (and PKCS#12 for the
+/// client, with password `secret`) to `dir`: `ca.pem`, `client.pem`, `client.key` and `client.p12`.
+pub struct TlsFixtures {
+  ca: (X509, PKey<Private>),
+  server: (X509, PKey<Private>),
+}
+
+impl TlsFixtures {
+  pub fn new(dir: &Path) -> Self {
+    let ca = certificate("cartero test CA", None);
+    let server = certificate("localhost", Some(&ca));
+    let client = certificate("cartero client", Some(&ca));
+    fs::write(dir.join("ca.pem"), ca.0.to_pem().unwrap()).unwrap();
+    fs::write(dir.join("client.pem"), client.0.to_pem().unwrap()).unwrap();
+    fs::write(dir.join("client.key"), client.1.private_key_to_pem_pkcs8().unwrap()).unwrap();
+    let p12 = Pkcs12::builder().build("secret", "client", &client.1, &client.0).unwrap();
+    fs::write(dir.join("client.p12"), p12.to_der().unwrap()).unwrap();
+    TlsFixtures { ca, server }
+  }
+
+  /// A server that only accepts clients with a certificate signed by the CA.
+  pub fn mtls_acceptor(&self) -> SslAcceptor {
+    let mut acceptor = SslAcceptor::mozilla_intermediate_v5(SslMethod::tls()).unwrap();
+    acceptor.set_private_key(&self.server.1).unwrap();
+    acceptor.set_certificate(&self.server.0).unwrap();
+    acceptor.cert_store_mut().add_cert(self.ca.0.clone()).unwrap();
+    acceptor.set_verify(SslVerifyMode::PEER | SslVerifyMode::FAIL_IF_NO_PEER_CERT);
+    acceptor.build()
+  }
+}
+
+/// A certificate for `name`, self-signed as a CA when there is no `issuer`.
+fn certificate(name: &str, issuer: Option<&(X509, PKey<Private>)>) -> (X509, PKey<Private>) {
+  let key = PKey::from_ec_key(EcKey::generate(&EcGroup::from_curve_name(Nid::X9_62_PRIME256V1).unwrap()).unwrap())
+    .unwrap();
+  let mut subject = X509NameBuilder::new().unwrap();
+  subject.append_entry_by_text("CN", name).unwrap();
+  let subject = subject.build();
+
+  let mut builder = X509::builder().unwrap();
+  builder.set_version(2).unwrap();
+  builder.set_serial_number(&BigNum::from_u32(rand::random()).unwrap().to_asn1_integer().unwrap()).unwrap();
+  builder.set_subject_name(&subject).unwrap();
+  builder.set_issuer_name(issuer.map(|(cert, _)| cert.subject_name()).unwrap_or(&subject)).unwrap();
+  builder.set_pubkey(&key).unwrap();
+  builder.set_not_before(&Asn1Time::days_from_now(0).unwrap()).unwrap();
+  builder.set_not_after(&Asn1Time::days_from_now(1).unwrap()).unwrap();
+  match issuer {
+    None => builder.append_extension(BasicConstraints::new().critical().ca().build().unwrap()).unwrap(),
+    Some((cert, _)) => {
+      let san = SubjectAlternativeName::new()
+        .dns("localhost")
+        .ip("127.0.0.1")
+        .build(&builder.x509v3_context(Some(cert), None))
+        .unwrap();
+      builder.append_extension(san).unwrap();
+      builder.append_extension(ExtendedKeyUsage::new().server_auth().client_auth().build().unwrap()).unwrap();
+    },
+  }
+  let signing_key = issuer.map(|(_, key)| key).unwrap_or(&key);
+  builder.sign(signing_key, MessageDigest::sha256()).unwrap();
+  (builder.build(), key)
+}
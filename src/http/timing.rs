@@ -0,0 +1,72 @@
+use serde::{Deserialize, Serialize};
+
+/// How long each phase of a request took, in milliseconds, measured on the connection the request was sent over. TLS
+/// is `None` over plain HTTP and Unix sockets. Requests through a proxy go with reqwest, which does not expose its
+/// connection, so DNS, connect and TLS are `None` for them and part of the time to first byte.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct Timing {
+  pub dns_ms: Option<u64>,
+  pub connect_ms: Option<u64>,
+  pub tls_ms: Option<u64>,
+  /// From sending the request until the response headers arrived
+  pub ttfb_ms: u64,
+  pub download_ms: u64,
+}
+
+impl Timing {
+  /// The phases that were measured, in order.
+  pub fn phases(&self) -> Vec<(&'static str, u64)> {
+    [
+      ("dns", self.dns_ms),
+      ("connect", self.connect_ms),
+      ("tls", self.tls_ms),
+      ("ttfb", Some(self.ttfb_ms)),
+      ("download", Some(self.download_ms)),
+    ]
+    .into_iter()
+    .filter_map(|(name, ms)| ms.map(|ms| (name, ms)))
+    .collect()
+  }
+
+  /// Splits `width` cells between the phases in proportion to their duration, as a waterfall. Every phase that
+  /// took any time gets at least one cell.
+  pub fn waterfall(&self, width: usize) -> Vec<(&'static str, u64, usize)> {
+    let phases = self.phases();
+    let total: u64 = phases.iter().map(|(_, ms)| ms).sum();
+    let visible = phases.iter().filter(|(_, ms)| *ms > 0).count();
+    if total == 0 || width < visible {
+      return phases.into_iter().map(|(name, ms)| (name, ms, 0)).collect();
+    }
+    // one cell is set aside for every visible phase, the rest is shared by duration
+    let shared = (width - visible) as u64;
+    let mut cells: Vec<(&'static str, u64, usize)> = phases
+      .into_iter()
+      .map(|(name, ms)| (name, ms, if ms > 0 { 1 + (ms * shared / total) as usize } else { 0 }))
+      .collect();
+    // rounding leftovers go to the longest phase
+    let used: usize = cells.iter().map(|(_, _, cells)| cells).sum();
+    if let Some(longest) = cells.iter_mut().max_by_key(|(_, ms, _)| *ms) {
+      longest.2 += width - used;
+    }
+    cells
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_waterfall() {
+    let timing = Timing { dns_ms: Some(0), connect_ms: Some(10), tls_ms: Some(30), ttfb_ms: 55, download_ms: 5 };
+    assert_eq!(
+      timing.waterfall(24),
+      vec![("dns", 0, 0), ("connect", 10, 3), ("tls", 30, 7), ("ttfb", 55, 12), ("download", 5, 2)]
+    );
+
+    let proxied = Timing { ttfb_ms: 90, download_ms: 10, ..Default::default() };
+    assert_eq!(proxied.waterfall(10), vec![("ttfb", 90, 9), ("download", 10, 1)]);
+  }
+}
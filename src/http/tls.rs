@@ -0,0 +1,395 @@
+use std::{
+  collections::HashMap,
+  fs,
+  net::{IpAddr, SocketAddr, TcpStream},
+  time::Duration,
+};
+
+use chrono::{DateTime, TimeZone, Utc};
+use openssl::{
+  asn1::{Asn1Time, Asn1TimeRef},
+  pkcs12::Pkcs12,
+  pkey::PKey,
+  ssl::{SslConnector, SslMethod, SslVerifyMode},
+  x509::{X509NameRef, X509},
+};
+use reqwest::{blocking::ClientBuilder, Certificate, Identity};
+use serde::{Deserialize, Serialize};
+
+use super::{replace_env_variables, Error};
+
+/// A client certificate for mutual TLS.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ClientCertificate {
+  /// A PEM certificate (chain) file and its PKCS#8 PEM key file
+  Pem { cert: String, key: String },
+  Pkcs12 {
+    file: String,
+    #[serde(default)]
+    password: String,
+  },
+}
+
+/// TLS settings of one environment (the active server).
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct TlsSettings {
+  /// PEM files with CA certificates to trust on top of the system ones
+  #[serde(default)]
+  pub ca_files: Vec<String>,
+  /// Accepts any certificate and hostname. Flagged in the status bar while on
+  #[serde(default)]
+  pub insecure: bool,
+  #[serde(default)]
+  pub client_cert: Option<ClientCertificate>,
+}
+
+impl TlsSettings {
+  /// These settings with env variables replaced in paths and passwords.
+  pub fn resolve(&self, env: &HashMap<String, String>) -> TlsSettings {
+    let replace = |value: &String| replace_env_variables(value, env);
+    TlsSettings {
+      ca_files: self.ca_files.iter().map(replace).collect(),
+      insecure: self.insecure,
+      client_cert: self.client_cert.as_ref().map(|cert| match cert {
+        ClientCertificate::Pem { cert, key } => ClientCertificate::Pem { cert: replace(cert), key: replace(key) },
+        ClientCertificate::Pkcs12 { file, password } => {
+          ClientCertificate::Pkcs12 { file: replace(file), password: replace(password) }
+        },
+      }),
+    }
+  }
+
+  /// Loads the certificates into `builder`. Fails when a file cannot be read or parsed.
+  pub fn apply(&self, mut builder: ClientBuilder) -> Result<ClientBuilder, Error> {
+    for file in &self.ca_files {
+      for cert in pem_blocks(&read(file)?, "CERTIFICATE") {
+        builder = builder.add_root_certificate(Certificate::from_pem(cert.as_bytes())?);
+      }
+    }
+    if self.insecure {
+      builder = builder.danger_accept_invalid_certs(true).danger_accept_invalid_hostnames(true);
+    }
+    let identity = match &self.client_cert {
+      Some(ClientCertificate::Pem { cert, key }) => Some(Identity::from_pkcs8_pem(&read(cert)?, &read(key)?)?),
+      Some(ClientCertificate::Pkcs12 { file, password }) => Some(Identity::from_pkcs12_der(&read(file)?, password)?),
+      None => None,
+    };
+    if let Some(identity) = identity {
+      builder = builder.identity(identity);
+    }
+    Ok(builder)
+  }
+
+  /// A connector trusting and presenting the same certificates as [`TlsSettings::apply`], for connections opened
+  /// outside of reqwest. Offers `alpn`.
+  pub fn connector(&self, alpn: &[&str]) -> Result<native_tls::TlsConnector, Error> {
+    let mut builder = native_tls::TlsConnector::builder();
+    for file in &self.ca_files {
+      for cert in pem_blocks(&read(file)?, "CERTIFICATE") {
+        builder.add_root_certificate(native_tls::Certificate::from_pem(cert.as_bytes())?);
+      }
+    }
+    builder.danger_accept_invalid_certs(self.insecure).danger_accept_invalid_hostnames(self.insecure);
+    match &self.client_cert {
+      Some(ClientCertificate::Pem { cert, key }) => {
+        builder.identity(native_tls::Identity::from_pkcs8(&read(cert)?, &read(key)?)?);
+      },
+      Some(ClientCertificate::Pkcs12 { file, password }) => {
+        builder.identity(native_tls::Identity::from_pkcs12(&read(file)?, password)?);
+      },
+      None => {},
+    }
+    builder.request_alpns(alpn);
+    Ok(builder.build()?)
+  }
+}
+
+/// The request's own TLS connection, as far as the client exposes it.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct ConnectionTls {
+  /// The server's certificate, without the rest of its chain
+  pub certificate: CertificateDetails,
+  /// `None` when the server did not pick a protocol, or when it is not known, as through a proxy
+  pub alpn: Option<String>,
+  /// Whether the certificate was checked, as it is unless the environment is insecure. A certificate that does not
+  /// pass fails the request
+  pub verified: bool,
+}
+
+/// What a separate handshake with the server negotiated, for what the client keeps to itself.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TlsDetails {
+  pub version: String,
+  pub cipher: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct CertificateDetails {
+  pub subject: String,
+  pub issuer: String,
+  pub sans: Vec<String>,
+  pub not_before: DateTime<Utc>,
+  pub not_after: DateTime<Utc>,
+}
+
+impl TlsSettings {
+  /// Opens a TLS connection to `addr` the way requests do, offering `alpn`, and tells its protocol version and cipher.
+  /// `host` is sent as SNI.
+  pub fn inspect(&self, host: &str, addr: SocketAddr, alpn: &[&str]) -> Result<TlsDetails, Error> {
+    let mut connector = SslConnector::builder(SslMethod::tls())?;
+    // only the parameters are wanted, the request already checked the certificate
+    connector.set_verify(SslVerifyMode::NONE);
+    match &self.client_cert {
+      Some(ClientCertificate::Pem { cert, key }) => {
+        let mut chain = X509::stack_from_pem(&read(cert)?)?.into_iter();
+        let leaf = chain.next().ok_or("No certificate in the client certificate file")?;
+        connector.set_certificate(&leaf)?;
+        for cert in chain {
+          connector.add_extra_chain_cert(cert)?;
+        }
+        let key = PKey::private_key_from_pem(&read(key)?)?;
+        connector.set_private_key(&key)?;
+      },
+      Some(ClientCertificate::Pkcs12 { file, password }) => {
+        let parsed = Pkcs12::from_der(&read(file)?)?.parse(password)?;
+        connector.set_certificate(&parsed.cert)?;
+        for cert in parsed.chain.into_iter().flatten() {
+          connector.add_extra_chain_cert(cert)?;
+        }
+        connector.set_private_key(&parsed.pkey)?;
+      },
+      None => {},
+    }
+    if !alpn.is_empty() {
+      let wire: Vec<u8> = alpn.iter().flat_map(|p| std::iter::once(p.len() as u8).chain(p.bytes())).collect();
+      connector.set_alpn_protos(&wire)?;
+    }
+
+    let timeout = Duration::from_secs(10);
+    let stream = TcpStream::connect_timeout(&addr, timeout)?;
+    stream.set_read_timeout(Some(timeout))?;
+    stream.set_write_timeout(Some(timeout))?;
+    let mut stream = connector.build().connect(host.trim_start_matches('[').trim_end_matches(']'), stream)?;
+    let ssl = stream.ssl();
+    let details = TlsDetails {
+      version: ssl.version_str().to_string(),
+      cipher: ssl.current_cipher().map(|cipher| cipher.name().to_string()).unwrap_or_default(),
+    };
+    let _ = stream.shutdown();
+    Ok(details)
+  }
+}
+
+/// Describes a DER certificate, as clients hand out the peer's.
+pub fn describe_der(der: &[u8]) -> Result<CertificateDetails, Error> {
+  let cert = X509::from_der(der)?;
+  describe_certificate(&cert)
+}
+
+fn describe_certificate(cert: &openssl::x509::X509Ref) -> Result<CertificateDetails, Error> {
+  let sans = cert
+    .subject_alt_names()
+    .map(|names| {
+      names
+        .iter()
+        .filter_map(|name| {
+          if let Some(dns) = name.dnsname() {
+            Some(format!("DNS:{}", dns))
+          } else if let Some(ip) = name.ipaddress() {
+            let ip = match ip.len() {
+              4 => IpAddr::from(<[u8; 4]>::try_from(ip).ok()?),
+              _ => IpAddr::from(<[u8; 16]>::try_from(ip).ok()?),
+            };
+            Some(format!("IP:{}", ip))
+          } else {
+            name.email().map(|email| format!("email:{}", email))
+          }
+        })
+        .collect()
+    })
+    .unwrap_or_default();
+  Ok(CertificateDetails {
+    subject: describe_name(cert.subject_name()),
+    issuer: describe_name(cert.issuer_name()),
+    sans,
+    not_before: to_date(cert.not_before())?,
+    not_after: to_date(cert.not_after())?,
+  })
+}
+
+/// `CN=example.com, O=Example` style.
+fn describe_name(name: &X509NameRef) -> String {
+  name
+    .entries()
+    .map(|entry| {
+      let key = entry.object().nid().short_name().unwrap_or("?");
+      let value = entry.data().as_utf8().map(|v| v.to_string()).unwrap_or_default();
+      format!("{}={}", key, value)
+    })
+    .collect::<Vec<_>>()
+    .join(", ")
+}
+
+fn to_date(time: &Asn1TimeRef) -> Result<DateTime<Utc>, Error> {
+  let diff = Asn1Time::from_unix(0)?.diff(time)?;
+  let seconds = diff.days as i64 * 86400 + diff.secs as i64;
+  Utc.timestamp_opt(seconds, 0).single().ok_or_else(|| "Invalid certificate date".into())
+}
+
+fn read(path: &str) -> Result<Vec<u8>, Error> {
+  fs::read(path).map_err(|e| format!("Could not read {}: {}", path, e).into())
+}
+
+/// Every `label` block of a PEM file, as a bundle may hold many certificates but `Certificate::from_pem` takes one.
+fn pem_blocks(pem: &[u8], label: &str) -> Vec<String> {
+  let (begin, end) = (format!("-----BEGIN {}-----", label), format!("-----END {}-----", label));
+  let pem = String::from_utf8_lossy(pem);
+  let mut blocks = Vec::new();
+  let mut rest = pem.as_ref();
+  while let Some(start) = rest.find(&begin) {
+    match rest[start..].find(&end) {
+      Some(len) => {
+        blocks.push(rest[start..start + len + end.len()].to_string());
+        rest = &rest[start + len + end.len()..];
+      },
+      None => break,
+    }
+  }
+  blocks
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::http::{
+    self,
+    client::ClientSettings,
+    proxy::ProxySettings,
+    test_server::{json_response, serve_socks, serve_tls, TlsFixtures},
+    PreparedRequest, TlsProbe,
+  };
+
+  /// Sends a GET to a fresh mTLS server, returning the status or the error and what the server received.
+  fn get(fixtures: &TlsFixtures, tls: &TlsSettings) -> (Result<u16, String>, String) {
+    let (base_url, server) = serve_tls(fixtures.mtls_acceptor(), vec![json_response("200 OK", "{}")]);
+    let proxy = ProxySettings { system: false, ..Default::default() };
+    let result = ClientSettings::default()
+      .build(&proxy, tls)
+      .map_err(|e| e.to_string())
+      .and_then(|client| client.get(format!("{}/secure", base_url)).send().map_err(|e| e.to_string()))
+      .map(|response| response.status().as_u16());
+    (result, server.join().unwrap().remove(0))
+  }
+
+  #[test]
+  fn test_private_ca_and_client_certificates() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixtures = TlsFixtures::new(dir.path());
+    let path = |file: &str| dir.path().join(file).to_string_lossy().to_string();
+    let pem = ClientCertificate::Pem { cert: path("client.pem"), key: path("client.key") };
+
+    // the private CA is not trusted by default
+    let (result, _) = get(&fixtures, &TlsSettings { client_cert: Some(pem.clone()), ..Default::default() });
+    assert!(result.is_err());
+
+    // trusted, but the server requires a client certificate
+    let trusted = TlsSettings { ca_files: vec![path("ca.pem")], ..Default::default() };
+    let (result, request) = get(&fixtures, &trusted);
+    assert!(result.is_err());
+    assert_eq!(request, "handshake failed");
+
+    let (result, request) = get(&fixtures, &TlsSettings { client_cert: Some(pem), ..trusted.clone() });
+    assert_eq!(result, Ok(200));
+    assert!(request.starts_with("GET /secure HTTP/1.1"));
+
+    let env = HashMap::from([(String::from("p12_password"), String::from("secret"))]);
+    let pkcs12 = ClientCertificate::Pkcs12 { file: path("client.p12"), password: String::from("{{p12_password}}") };
+    let insecure = TlsSettings { insecure: true, client_cert: Some(pkcs12), ..Default::default() };
+    let (result, _) = get(&fixtures, &insecure.resolve(&env));
+    assert_eq!(result, Ok(200));
+  }
+
+  /// The certificates the mTLS test server trusts and is trusted with.
+  fn trusted(path: impl Fn(&str) -> String) -> TlsSettings {
+    TlsSettings {
+      ca_files: vec![path("ca.pem")],
+      client_cert: Some(ClientCertificate::Pem { cert: path("client.pem"), key: path("client.key") }),
+      ..Default::default()
+    }
+  }
+
+  fn assert_is_the_server_certificate(connection: &ConnectionTls) {
+    let cert = &connection.certificate;
+    assert_eq!(cert.subject, "CN=localhost");
+    assert_eq!(cert.issuer, "CN=cartero test CA");
+    assert_eq!(cert.sans, vec![String::from("DNS:localhost"), String::from("IP:127.0.0.1")]);
+    assert!(cert.not_before < cert.not_after);
+    assert!(connection.verified);
+  }
+
+  #[test]
+  fn test_send_describes_the_tls_connection() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixtures = TlsFixtures::new(dir.path());
+    let path = |file: &str| dir.path().join(file).to_string_lossy().to_string();
+    // one connection for the request, one for the handshake telling the version and cipher
+    let (base_url, server) = serve_tls(fixtures.mtls_acceptor(), vec![json_response("200 OK", "{}"); 2]);
+    let request = PreparedRequest {
+      url: format!("{}/secure", base_url),
+      proxy: ProxySettings { system: false, ..Default::default() },
+      tls: trusted(path),
+      ..Default::default()
+    };
+    let response = http::send(request);
+
+    assert_eq!(response.status, Some(200));
+    assert!(response.remote_addr.unwrap().starts_with("127.0.0.1:"));
+    let connection = response.tls_connection.unwrap();
+    assert_is_the_server_certificate(&connection);
+    assert_eq!(connection.alpn, None);
+    let timing = response.timing.unwrap();
+    assert!(timing.dns_ms.is_some() && timing.connect_ms.is_some() && timing.tls_ms.is_some());
+
+    assert!(response.tls.is_none());
+    let details = match response.tls_probe {
+      Some(TlsProbe::Direct(target)) => target.handshake().unwrap(),
+      probe => panic!("expected a direct TLS target, got {:?}", probe),
+    };
+    server.join().unwrap();
+    assert!(details.version.starts_with("TLSv1."));
+    assert!(!details.cipher.is_empty());
+  }
+
+  #[test]
+  fn test_send_through_a_socks_proxy_describes_the_certificate() {
+    let dir = tempfile::tempdir().unwrap();
+    let fixtures = TlsFixtures::new(dir.path());
+    let path = |file: &str| dir.path().join(file).to_string_lossy().to_string();
+    let (base_url, server) = serve_tls(fixtures.mtls_acceptor(), vec![json_response("200 OK", "{}")]);
+    let port = url::Url::parse(&base_url).unwrap().port().unwrap();
+    let (proxy_url, proxy) = serve_socks(port);
+    let request = PreparedRequest {
+      url: format!("{}/secure", base_url),
+      proxy: ProxySettings { system: false, https: Some(proxy_url), ..Default::default() },
+      tls: trusted(path),
+      ..Default::default()
+    };
+    let response = http::send(request);
+
+    assert_eq!(response.status, Some(200));
+    assert_eq!(proxy.join().unwrap(), format!("localhost:{}", port));
+    server.join().unwrap();
+    assert_is_the_server_certificate(&response.tls_connection.unwrap());
+    assert!(matches!(response.tls_probe, Some(TlsProbe::Proxied)));
+  }
+
+  #[test]
+  fn test_missing_ca_file_is_an_error() {
+    let tls = TlsSettings { ca_files: vec![String::from("/nonexistent/ca.pem")], ..Default::default() };
+    let error = ClientSettings::default().build(&ProxySettings::default(), &tls).unwrap_err();
+    assert!(error.to_string().starts_with("Could not read /nonexistent/ca.pem"));
+  }
+}
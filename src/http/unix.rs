@@ -0,0 +1,214 @@
+#[cfg(unix)]
+use std::{os::unix::net::UnixStream, time::Instant};
+use std::{
+  io::{BufRead, BufReader, Read, Write},
+  time::Duration,
+};
+
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_LENGTH, HOST, TRANSFER_ENCODING};
+
+use super::Error;
+
+/// Servers of this form send requests over the Unix socket at the given path, e.g. `unix:///var/run/docker.sock`.
+pub const SCHEME: &str = "unix://";
+
+/// A request to send over a Unix socket, as HTTP/1.1.
+pub struct UnixRequest<'a> {
+  pub socket: &'a str,
+  pub method: &'a str,
+  /// Path and query, e.g. `/containers/json?all=true`
+  pub target: &'a str,
+  pub headers: &'a HeaderMap,
+  pub body: &'a str,
+  pub timeout: Option<Duration>,
+}
+
+pub struct UnixResponse {
+  pub status: u16,
+  pub headers: HeaderMap,
+  pub body: String,
+  pub connect: Duration,
+  pub ttfb: Duration,
+  pub download: Duration,
+}
+
+#[cfg(unix)]
+pub fn send(request: UnixRequest) -> Result<UnixResponse, Error> {
+  let started = Instant::now();
+  let mut stream = UnixStream::connect(request.socket)
+    .map_err(|e| format!("Could not connect to {}: {}", request.socket, e))?;
+  let connect = started.elapsed();
+  stream.set_read_timeout(request.timeout)?;
+  stream.set_write_timeout(request.timeout)?;
+
+  let sent_at = Instant::now();
+  stream.write_all(&encode_request(&request))?;
+  let mut reader = BufReader::new(stream);
+  let (status, headers) = read_head(&mut reader)?;
+  let ttfb = sent_at.elapsed();
+  let download_started = Instant::now();
+  let body = read_body(&mut reader, &headers, request.method == "HEAD" || status == 204 || status == 304)?;
+  Ok(UnixResponse {
+    status,
+    headers,
+    body: String::from_utf8_lossy(&body).to_string(),
+    connect,
+    ttfb,
+    download: download_started.elapsed(),
+  })
+}
+
+#[cfg(not(unix))]
+pub fn send(_request: UnixRequest) -> Result<UnixResponse, Error> {
+  Err("Unix sockets are not supported on this platform".into())
+}
+
+/// The request head and body. There is no host to name, so `Host` is `localhost` unless the request sets it, and
+/// the connection is closed after the response.
+fn encode_request(request: &UnixRequest) -> Vec<u8> {
+  let mut head = format!("{} {} HTTP/1.1\r\n", request.method, request.target);
+  if !request.headers.contains_key(HOST) {
+    head.push_str("Host: localhost\r\n");
+  }
+  for (name, value) in request.headers {
+    if name != CONTENT_LENGTH {
+      head.push_str(&format!("{}: {}\r\n", name, String::from_utf8_lossy(value.as_bytes())));
+    }
+  }
+  if !request.body.is_empty() || request.method != "GET" {
+    head.push_str(&format!("Content-Length: {}\r\n", request.body.len()));
+  }
+  head.push_str("Connection: close\r\n\r\n");
+  let mut bytes = head.into_bytes();
+  bytes.extend_from_slice(request.body.as_bytes());
+  bytes
+}
+
+fn read_line(reader: &mut impl BufRead) -> Result<String, Error> {
+  let mut line = String::new();
+  if reader.read_line(&mut line)? == 0 {
+    return Err("Connection closed before the response was complete".into());
+  }
+  Ok(line.trim_end_matches(['\r', '\n']).to_string())
+}
+
+/// Reads the status line and headers.
+fn read_head(reader: &mut impl BufRead) -> Result<(u16, HeaderMap), Error> {
+  let status_line = read_line(reader)?;
+  let status = status_line
+    .split_whitespace()
+    .nth(1)
+    .and_then(|code| code.parse::<u16>().ok())
+    .ok_or_else(|| format!("Invalid status line: {}", status_line))?;
+  let mut headers = HeaderMap::new();
+  loop {
+    let line = read_line(reader)?;
+    if line.is_empty() {
+      break;
+    }
+    if let Some((name, value)) = line.split_once(':') {
+      headers.append(HeaderName::from_bytes(name.trim().as_bytes())?, HeaderValue::from_str(value.trim())?);
+    }
+  }
+  // interim responses such as 100 Continue are followed by the real one
+  if (100..200).contains(&status) {
+    return read_head(reader);
+  }
+  Ok((status, headers))
+}
+
+/// Reads a chunked, sized or close-delimited body.
+fn read_body(reader: &mut impl BufRead, headers: &HeaderMap, no_body: bool) -> Result<Vec<u8>, Error> {
+  let mut body = Vec::new();
+  if no_body {
+    return Ok(body);
+  }
+  let chunked = headers
+    .get_all(TRANSFER_ENCODING)
+    .iter()
+    .any(|value| value.to_str().map(|v| v.to_lowercase().contains("chunked")).unwrap_or(false));
+  if chunked {
+    loop {
+      let size_line = read_line(reader)?;
+      let size = usize::from_str_radix(size_line.split(';').next().unwrap_or("").trim(), 16)
+        .map_err(|_| format!("Invalid chunk size: {}", size_line))?;
+      if size == 0 {
+        // trailers, up to the blank line
+        while !read_line(reader)?.is_empty() {}
+        break;
+      }
+      let mut chunk = vec![0; size];
+      reader.read_exact(&mut chunk)?;
+      body.extend_from_slice(&chunk);
+      read_line(reader)?;
+    }
+  } else if let Some(length) = headers.get(CONTENT_LENGTH).and_then(|v| v.to_str().ok()?.parse::<usize>().ok()) {
+    body.resize(length, 0);
+    reader.read_exact(&mut body)?;
+  } else {
+    reader.read_to_end(&mut body)?;
+  }
+  Ok(body)
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+  use std::{collections::HashMap, os::unix::net::UnixListener, thread};
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::{
+    http::{self, Context},
+    repository::local_storage::RequestInput,
+  };
+
+  #[test]
+  fn test_chunked_body() {
+    let raw = "HTTP/1.1 100 Continue\r\n\r\nHTTP/1.1 200 OK\r\nTransfer-Encoding: chunked\r\n\r\n\
+               5\r\nhello\r\n7;ext=1\r\n, world\r\n0\r\nX-Trailer: 1\r\n\r\n";
+    let mut reader = BufReader::new(raw.as_bytes());
+    let (status, headers) = read_head(&mut reader).unwrap();
+    assert_eq!(status, 200);
+    assert_eq!(read_body(&mut reader, &headers, false).unwrap(), b"hello, world");
+  }
+
+  #[test]
+  fn test_send_over_unix_socket() {
+    let dir = tempfile::tempdir().unwrap();
+    let socket = dir.path().join("docker.sock");
+    let listener = UnixListener::bind(&socket).unwrap();
+    let server = thread::spawn(move || {
+      let (mut stream, _) = listener.accept().unwrap();
+      let mut request = vec![0; 1024];
+      let read = stream.read(&mut request).unwrap();
+      let body = r#"[{"Id":"abc"}]"#;
+      let response = format!("HTTP/1.1 200 OK\r\nContent-Length: {}\r\nApi-Version: 1.43\r\n\r\n{}", body.len(), body);
+      stream.write_all(response.as_bytes()).unwrap();
+      String::from_utf8_lossy(&request[..read]).to_string()
+    });
+
+    let socket = socket.to_string_lossy();
+    let context = Context {
+      server: format!("{}{{{{socket}}}}", SCHEME),
+      env: HashMap::from([(String::from("socket"), socket.to_string())]),
+      ..Default::default()
+    };
+    let request = RequestInput {
+      path: String::from("/containers/json"),
+      query: String::from("all=true"),
+      ..Default::default()
+    };
+    let prepared = http::prepare(&request, &context).unwrap();
+    assert_eq!(prepared.unix_socket, Some(socket.to_string()));
+    let response = http::send(prepared);
+    let received = server.join().unwrap();
+
+    assert!(received.starts_with("GET /containers/json?all=true HTTP/1.1\r\nHost: localhost\r\n"));
+    assert_eq!(response.status, Some(200));
+    assert_eq!(response.body, r#"[{"Id":"abc"}]"#);
+    assert_eq!(response.header_map.get("api-version").unwrap(), "1.43");
+    assert_eq!(response.remote_addr, Some(socket.to_string()));
+    assert_eq!(response.raw_request, Some(received.replace("\r\n", "\n")));
+  }
+}
@@ -0,0 +1,138 @@
+use reqwest::{
+  header::{HeaderMap, CONTENT_LENGTH, HOST},
+  StatusCode,
+};
+use url::{Position, Url};
+
+use super::{direct, unix_target, HttpMethod, PreparedRequest, ReqResponse};
+
+/// The request as an HTTP/1.1 transcript: request line, headers, a blank line and the body. Headers the client adds
+/// on its own (`host` and [`direct::default_headers`]) are filled in as it sends them, and GET requests have no body,
+/// see [`PreparedRequest::sent_body`]. Over a Unix socket it is exactly what is written.
+pub fn request(request: &PreparedRequest) -> String {
+  let body = request.sent_body();
+  if let Some(socket) = &request.unix_socket {
+    return match unix_target(socket, &request.url) {
+      Ok(target) => unix_request(request, &target),
+      Err(_) => format!("{} {} HTTP/1.1\n\n{}", request.method.to_string(), request.url, body),
+    };
+  }
+  let url = match Url::parse(&request.url) {
+    Ok(url) => url,
+    Err(_) => return format!("{} {} HTTP/1.1\n\n{}", request.method.to_string(), request.url, body),
+  };
+  let mut lines =
+    vec![format!("{} {} HTTP/1.1", request.method.to_string(), &url[Position::BeforePath..Position::AfterQuery])];
+  if !request.headers.contains_key(HOST) {
+    lines.push(format!("host: {}", &url[Position::BeforeHost..Position::AfterPort]));
+  }
+  lines.extend(header_lines(&request.headers));
+  lines.extend(header_lines(&direct::default_headers(request)));
+  format!("{}\n\n{}", lines.join("\n"), body)
+}
+
+fn unix_request(request: &PreparedRequest, target: &str) -> String {
+  let mut lines = vec![format!("{} {} HTTP/1.1", request.method.to_string(), target)];
+  if !request.headers.contains_key(HOST) {
+    lines.push(String::from("Host: localhost"));
+  }
+  let mut headers = request.headers.clone();
+  headers.remove(CONTENT_LENGTH);
+  lines.extend(header_lines(&headers));
+  let body = request.sent_body();
+  if !body.is_empty() || request.method != HttpMethod::GET {
+    lines.push(format!("Content-Length: {}", body.len()));
+  }
+  lines.push(String::from("Connection: close"));
+  format!("{}\n\n{}", lines.join("\n"), body)
+}
+
+/// The response as an HTTP/1.1 transcript. The client takes `content-encoding` and `content-length` out of responses it
+/// decompresses, and the body is shown decoded. Without a status there was no response, and the error is returned.
+pub fn response(response: &ReqResponse) -> String {
+  let status = match response.status {
+    Some(status) => status,
+    None => return response.body.clone(),
+  };
+  let reason = StatusCode::from_u16(status).ok().and_then(|s| s.canonical_reason()).unwrap_or_default();
+  let mut lines = vec![format!("HTTP/1.1 {} {}", status, reason)];
+  if response.header_map.is_empty() {
+    // responses read back from the history only have the headers as text
+    lines.extend(response.headers.lines().map(str::trim).filter(|line| !line.is_empty()).map(String::from));
+  } else {
+    lines.extend(header_lines(&response.header_map));
+  }
+  format!("{}\n\n{}", lines.join("\n"), response.body)
+}
+
+/// Both sides of the exchange, one after the other.
+pub fn transcript(response: &ReqResponse) -> String {
+  let request = response.raw_request.as_deref().unwrap_or("(request not recorded)");
+  format!("{}\n\n{}", request, self::response(response))
+}
+
+fn header_lines(headers: &HeaderMap) -> impl Iterator<Item = String> + '_ {
+  headers.iter().map(|(name, value)| format!("{}: {}", name, String::from_utf8_lossy(value.as_bytes())))
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+  use reqwest::header::{HeaderValue, AUTHORIZATION, CONTENT_TYPE};
+
+  use super::*;
+  use crate::http::{
+    self,
+    client::ClientSettings,
+    proxy::ProxySettings,
+    test_server::{json_response, serve},
+  };
+
+  #[test]
+  fn test_transcript_matches_what_was_received() {
+    let (base_url, server) = serve(vec![json_response("201 Created", r#"{"id":1}"#)]);
+    let mut headers = HeaderMap::new();
+    headers.insert(AUTHORIZATION, HeaderValue::from_static("Bearer abc"));
+    headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+    let request = PreparedRequest {
+      method: HttpMethod::POST,
+      url: format!("{}/users?active=true&name=J%C3%BCrgen", base_url),
+      headers,
+      body: String::from(r#"{"name":"Jürgen"}"#),
+      client: ClientSettings { brotli: Some(false), user_agent: Some(String::from("cartero")), ..Default::default() },
+      proxy: ProxySettings { system: false, ..Default::default() },
+      ..Default::default()
+    };
+    let response = http::send(request);
+    let received = server.join().unwrap().remove(0);
+
+    let raw_request = response.raw_request.clone().unwrap();
+    assert!(raw_request.starts_with("POST /users?active=true&name=J%C3%BCrgen HTTP/1.1\n"));
+    assert!(raw_request.contains("\naccept-encoding: gzip, deflate\n"));
+    let lines = |text: &str| {
+      let (head, body) = text.split_once("\n\n").unwrap();
+      let mut lines: Vec<String> = head.lines().map(str::to_lowercase).collect();
+      lines.sort();
+      (lines, body.to_string())
+    };
+    assert_eq!(lines(&raw_request), lines(&received.replace("\r\n", "\n")));
+
+    let transcript = transcript(&response);
+    assert!(transcript.contains(&format!("{}\n\nHTTP/1.1 201 Created\n", raw_request)));
+    assert!(transcript.ends_with("\n\n{\"id\":1}"));
+    assert!(!serde_json::to_string(&response).unwrap().contains("Bearer abc"));
+    let timing = response.timing.unwrap();
+    assert!(timing.connect_ms.is_some() && timing.tls_ms.is_none());
+  }
+
+  #[test]
+  fn test_get_has_no_body() {
+    let request = PreparedRequest {
+      method: HttpMethod::GET,
+      url: String::from("https://api.example.com/users"),
+      body: String::from("ignored"),
+      ..Default::default()
+    };
+    assert!(self::request(&request).ends_with("\n\n"));
+  }
+}
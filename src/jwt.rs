@@ -0,0 +1,159 @@
+use std::collections::HashMap;
+
+use base64::{engine::general_purpose::URL_SAFE_NO_PAD, Engine};
+use chrono::{DateTime, Local, TimeZone, Utc};
+use fancy_regex::Regex;
+use hmac::Hmac;
+use serde_json::Value;
+use sha2::{Sha256, Sha384, Sha512};
+
+use crate::auth::hmac_signature::mac;
+
+/// A decoded, not necessarily valid, JSON Web Token.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Jwt {
+  pub header: Value,
+  pub claims: Value,
+  signing_input: String,
+  signature: Vec<u8>,
+}
+
+impl Jwt {
+  /// Decodes the header and claims of a compact JWS, or `None` when `token` is not one.
+  pub fn decode(token: &str) -> Option<Self> {
+    let token = token.trim().trim_start_matches("Bearer ").trim();
+    let mut parts = token.split('.');
+    let (header, claims, signature) = (parts.next()?, parts.next()?, parts.next()?);
+    if parts.next().is_some() {
+      return None;
+    }
+    let decode_json = |part: &str| -> Option<Value> {
+      let value: Value = serde_json::from_slice(&URL_SAFE_NO_PAD.decode(part).ok()?).ok()?;
+      value.is_object().then_some(value)
+    };
+    Some(Jwt {
+      header: decode_json(header)?,
+      claims: decode_json(claims)?,
+      signing_input: format!("{}.{}", header, claims),
+      signature: URL_SAFE_NO_PAD.decode(signature).ok()?,
+    })
+  }
+
+  pub fn algorithm(&self) -> &str {
+    self.header.get("alg").and_then(Value::as_str).unwrap_or("none")
+  }
+
+  /// A NumericDate claim such as `exp`, `iat` or `nbf`.
+  pub fn timestamp(&self, claim: &str) -> Option<DateTime<Utc>> {
+    let seconds = self.claims.get(claim)?.as_f64()?;
+    Utc.timestamp_opt(seconds as i64, 0).single()
+  }
+
+  pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+    self.timestamp("exp").map(|exp| exp <= now).unwrap_or(false)
+  }
+
+  /// Checks an HS256/384/512 signature against `secret`. `None` when the token is not HMAC signed.
+  pub fn verify_hmac(&self, secret: &str) -> Option<bool> {
+    let key = secret.as_bytes();
+    let expected = match self.algorithm() {
+      "HS256" => mac::<Hmac<Sha256>>(key, self.signing_input.as_bytes()),
+      "HS384" => mac::<Hmac<Sha384>>(key, self.signing_input.as_bytes()),
+      "HS512" => mac::<Hmac<Sha512>>(key, self.signing_input.as_bytes()),
+      _ => return None,
+    };
+    Some(expected == self.signature)
+  }
+}
+
+/// Every JWT-looking string inside `text`.
+pub fn find_in(text: &str) -> Vec<String> {
+  let re = Regex::new(r"eyJ[A-Za-z0-9_-]*\.eyJ[A-Za-z0-9_-]*\.[A-Za-z0-9_-]*").unwrap();
+  re.find_iter(text)
+    .filter_map(|m| m.ok())
+    .map(|m| m.as_str().to_string())
+    .filter(|token| Jwt::decode(token).is_some())
+    .collect()
+}
+
+/// Env keys holding a JWT whose `exp` is in the past, sorted.
+pub fn expired_env_tokens(env: &HashMap<String, String>, now: DateTime<Utc>) -> Vec<String> {
+  let mut expired: Vec<String> = env
+    .iter()
+    .filter(|(_, value)| Jwt::decode(value).map(|jwt| jwt.is_expired(now)).unwrap_or(false))
+    .map(|(key, _)| key.clone())
+    .collect();
+  expired.sort();
+  expired
+}
+
+/// `1d 2h`, `3h 4m`, `5m 6s` or `7s`.
+pub fn format_duration(seconds: i64) -> String {
+  let seconds = seconds.abs();
+  let (days, hours, minutes, secs) = (seconds / 86400, seconds % 86400 / 3600, seconds % 3600 / 60, seconds % 60);
+  match (days, hours, minutes) {
+    (0, 0, 0) => format!("{}s", secs),
+    (0, 0, _) => format!("{}m {}s", minutes, secs),
+    (0, _, _) => format!("{}h {}m", hours, minutes),
+    _ => format!("{}d {}h", days, hours),
+  }
+}
+
+/// A claim timestamp as a local date, with how far it is from `now`.
+pub fn describe_timestamp(claim: &str, timestamp: DateTime<Utc>, now: DateTime<Utc>) -> String {
+  let date = timestamp.with_timezone(&Local).format("%Y-%m-%d %H:%M:%S");
+  let delta = (timestamp - now).num_seconds();
+  let relative = match (claim, delta >= 0) {
+    ("exp", true) => format!("expires in {}", format_duration(delta)),
+    ("exp", false) => format!("expired {} ago", format_duration(delta)),
+    (_, true) => format!("in {}", format_duration(delta)),
+    (_, false) => format!("{} ago", format_duration(delta)),
+  };
+  format!("{} ({})", date, relative)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  // {"alg":"HS256","typ":"JWT"}.{"sub":"1234567890","name":"John Doe","iat":1516239022},
+  // signed with "your-256-bit-secret"
+  const TOKEN: &str = "eyJhbGciOiJIUzI1NiIsInR5cCI6IkpXVCJ9.\
+                       eyJzdWIiOiIxMjM0NTY3ODkwIiwibmFtZSI6IkpvaG4gRG9lIiwiaWF0IjoxNTE2MjM5MDIyfQ.\
+                       SflKxwRJSMeKKF2QT4fwpMeJf36POk6yJV_adQssw5c";
+
+  #[test]
+  fn test_decode_and_verify() {
+    let jwt = Jwt::decode(&format!("Bearer {}", TOKEN)).unwrap();
+    assert_eq!(jwt.algorithm(), "HS256");
+    assert_eq!(jwt.claims["name"], "John Doe");
+    assert_eq!(jwt.timestamp("iat").unwrap().timestamp(), 1516239022);
+    assert_eq!(jwt.verify_hmac("your-256-bit-secret"), Some(true));
+    assert_eq!(jwt.verify_hmac("nope"), Some(false));
+    assert_eq!(Jwt::decode("not.a.jwt"), None);
+  }
+
+  #[test]
+  fn test_expired_env_tokens() {
+    let now = Utc.timestamp_opt(2_000_000_000, 0).unwrap();
+    let claims = |exp: i64| URL_SAFE_NO_PAD.encode(format!(r#"{{"exp":{}}}"#, exp));
+    let token = |exp: i64| format!("eyJhbGciOiJub25lIn0.{}.", claims(exp));
+    let env = HashMap::from([
+      (String::from("jwt"), token(1_999_999_000)),
+      (String::from("refresh_jwt"), token(2_000_086_400)),
+      (String::from("user"), String::from("goku")),
+    ]);
+    assert_eq!(expired_env_tokens(&env, now), vec![String::from("jwt")]);
+    assert_eq!(find_in(&format!(r#"{{"access_token":"{}"}}"#, token(1))), vec![token(1)]);
+  }
+
+  #[test]
+  fn test_format_duration() {
+    assert_eq!(format_duration(42), "42s");
+    assert_eq!(format_duration(-310), "5m 10s");
+    assert_eq!(format_duration(3 * 3600 + 120), "3h 2m");
+    assert_eq!(format_duration(90000), "1d 1h");
+  }
+}
@@ -5,15 +5,23 @@
 // ANCHOR: all
 pub mod action;
 pub mod app;
+pub mod auth;
 pub mod cli;
 pub mod components;
 pub mod config;
+pub mod diff;
+pub mod error;
+pub mod http;
+pub mod jwt;
+pub mod parsing;
+pub mod refresh;
 pub mod repository;
+pub mod snapshot;
 pub mod tui;
 pub mod utils;
 
 use clap::Parser;
-use cli::Cli;
+use cli::{Cli, Commands};
 use color_eyre::eyre::Result;
 
 use crate::{
@@ -27,6 +35,10 @@ async fn tokio_main() -> Result<()> {
   initialize_panic_handler()?;
 
   let args = Cli::parse();
+  if let Some(Commands::Snapshot { update_snapshots }) = args.command {
+    return snapshot::run(update_snapshots).await;
+  }
+
   let mut app = App::new(args.tick_rate, args.frame_rate)?;
   app.run().await?;
 
@@ -0,0 +1,104 @@
+use std::{
+  collections::HashMap,
+  io::Write,
+  process::{Command, Stdio},
+};
+
+use serde_json::Value;
+use tracing::{debug, error};
+
+/// Runs `jq <filter>` over `input` and returns its stdout, or `None` when jq is missing or the filter fails.
+pub fn run_jq(filter: &str, input: &str) -> Option<String> {
+  let mut child = match Command::new("jq")
+    .arg(filter)
+    .stdin(Stdio::piped())
+    .stdout(Stdio::piped())
+    .stderr(Stdio::null())
+    .spawn()
+  {
+    Ok(child) => child,
+    Err(e) => {
+      error!("Failed to start jq: {:?}", e);
+      return None;
+    },
+  };
+
+  if let Some(mut stdin) = child.stdin.take() {
+    if let Err(e) = stdin.write_all(input.as_bytes()) {
+      error!("Failed to write to jq stdin: {:?}", e);
+    }
+  }
+
+  match child.wait_with_output() {
+    Ok(output) if output.status.success() => Some(String::from_utf8_lossy(&output.stdout).to_string()),
+    _ => None,
+  }
+}
+
+pub fn value_to_string(value: &Value) -> String {
+  match value {
+    Value::String(s) => s.to_string(),
+    Value::Null => "null".to_string(),
+    _ => value.to_string(),
+  }
+}
+
+/// Evaluates a single parsing rule expression against a response body. Expressions starting with `/` are JSON
+/// pointers, anything else is handed to jq.
+pub fn extract(expression: &str, body: &str) -> Option<String> {
+  if expression.starts_with('/') {
+    let json = serde_json::from_str::<Value>(body).ok()?;
+    return json.pointer(expression).map(value_to_string);
+  }
+  let output = run_jq(expression, body)?;
+  let output = output.trim();
+  match serde_json::from_str::<Value>(output) {
+    Ok(Value::Null) => None,
+    Ok(value) => Some(value_to_string(&value)),
+    Err(_) => Some(output.to_string()),
+  }
+}
+
+/// The env keys `key -> expression` rules set, skipping invalid rules.
+pub fn rule_keys(rules: &str) -> impl Iterator<Item = &str> {
+  rules.lines().filter_map(|rule| rule.split_once("->")).map(|(key, _)| key.trim())
+}
+
+/// Applies `key -> expression` rules, one per line, storing every extracted value into `env`.
+pub fn apply_parsing_rules(rules: &str, body: &str, env: &mut HashMap<String, String>) {
+  for rule in rules.lines().filter(|l| !l.trim().is_empty()) {
+    let (key, expression) = match rule.split_once("->") {
+      Some((key, expression)) => (key.trim(), expression.trim()),
+      None => {
+        error!("Invalid parsing rule: {}", rule);
+        continue;
+      },
+    };
+    match extract(expression, body) {
+      Some(value) => {
+        debug!("Parsing rule set {}", key);
+        env.insert(key.to_string(), value);
+      },
+      None => debug!("Parsing rule {} did not match", key),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_apply_parsing_rules_with_pointers() {
+    let mut env = HashMap::new();
+    let body = r#"{ "token": "abc", "refresh_token": "def", "expires_in": 3600 }"#;
+    let rules = "jwt -> /token\nrefresh_jwt -> /refresh_token\nttl -> /expires_in\nnope -> /missing\n";
+    apply_parsing_rules(rules, body, &mut env);
+    assert_eq!(env.get("jwt").unwrap(), "abc");
+    assert_eq!(env.get("refresh_jwt").unwrap(), "def");
+    assert_eq!(env.get("ttl").unwrap(), "3600");
+    assert!(!env.contains_key("nope"));
+  }
+}
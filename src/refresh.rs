@@ -0,0 +1,268 @@
+use serde::{Deserialize, Serialize};
+use tracing::info;
+
+use crate::{
+  auth::Auth,
+  error::Error as AppError,
+  http::{
+    self,
+    dispatch::{dispatch, Sent},
+    Context, ReqResponse,
+  },
+  parsing,
+  repository::local_storage::RequestInput,
+};
+
+/// Marks one saved request as the environment's token refresh request.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct RefreshConfig {
+  pub request_id: String,
+  #[serde(default)]
+  pub trigger: RefreshTrigger,
+}
+
+/// When a response should be treated as an expired session.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RefreshTrigger {
+  Status(u16),
+  /// jq filter evaluated on the response body, triggers when it outputs `true`
+  Jq(String),
+}
+
+impl Default for RefreshTrigger {
+  fn default() -> Self {
+    RefreshTrigger::Status(401)
+  }
+}
+
+impl RefreshTrigger {
+  pub fn matches(&self, response: &ReqResponse) -> bool {
+    match self {
+      RefreshTrigger::Status(status) => response.status == Some(*status),
+      RefreshTrigger::Jq(filter) => {
+        parsing::run_jq(filter, &response.body).map(|output| output.trim() == "true").unwrap_or(false)
+      },
+    }
+  }
+}
+
+/// What [`send_with_refresh`] sent.
+pub struct Outcome {
+  /// Everything sent, in order; the last entry is the response to show
+  pub sent: Vec<Sent>,
+  /// Why the refresh request, or the retry after it, could not be sent. What was sent before is kept, so the
+  /// response that hit the trigger is still shown
+  pub refresh_error: Option<http::Error>,
+}
+
+/// Sends `request` and, when its response hits the refresh trigger, runs the refresh request, applies its parsing
+/// rules to the env and retries the original once. The refresh request also runs first when the env lacks variables
+/// of the request that its parsing rules set, like a token on the first send. Parsing rules of every sent request are
+/// applied to the env.
+///
+/// A refresh that fails after the request was sent is reported in [`Outcome::refresh_error`]. Blocking.
+pub fn send_with_refresh(
+  request: &RequestInput,
+  context: &mut Context,
+  refresh: Option<(&RequestInput, &RefreshTrigger)>,
+) -> Result<Outcome, http::Error> {
+  let refresh = refresh.filter(|(refresh_request, _)| refresh_request.id != request.id);
+  let mut sent = match (send_one(request, context), refresh) {
+    (Err(e), Some((refresh_request, _))) if sets_unresolved(refresh_request, &e) => {
+      info!("Running refresh request for the variables the env lacks");
+      let mut sent = send_one(refresh_request, context)?;
+      sent.extend(send_one(request, context)?);
+      return Ok(Outcome { sent, refresh_error: None });
+    },
+    (result, _) => result?,
+  };
+
+  let mut refresh_error = None;
+  if let Some((refresh_request, trigger)) = refresh {
+    if sent.last().map(|s| trigger.matches(&s.response)).unwrap_or(false) {
+      info!("Refresh trigger hit, running refresh request");
+      let refreshed = send_one(refresh_request, context).and_then(|refreshed| {
+        sent.extend(refreshed);
+        send_one(request, context)
+      });
+      match refreshed {
+        Ok(retried) => sent.extend(retried),
+        Err(e) => refresh_error = Some(e),
+      }
+    }
+  }
+
+  Ok(Outcome { sent, refresh_error })
+}
+
+/// Whether `error` is about variables missing from the env that the parsing rules of `refresh_request` all set.
+fn sets_unresolved(refresh_request: &RequestInput, error: &http::Error) -> bool {
+  match error.downcast_ref::<AppError>() {
+    Some(AppError::UnresolvedVariables(names)) => {
+      let keys: Vec<&str> = parsing::rule_keys(&refresh_request.parsing_rules).collect();
+      names.iter().all(|name| keys.contains(&name.as_str()))
+    },
+    _ => false,
+  }
+}
+
+/// Sends a single request, plus the authenticated retry when the auth answers a challenge (Digest).
+fn send_one(request: &RequestInput, context: &mut Context) -> Result<Vec<Sent>, http::Error> {
+  let auth = context.auth_for(request).cloned();
+  if let Some(auth) = &auth {
+    auth.authorize(context)?;
+  }
+  let prepared = http::prepare(request, context)?;
+  let mut sent = vec![dispatch(request, context, prepared.clone())];
+
+  if let Some(Auth::Digest(digest)) = &auth {
+    if let Some(retry) = digest.respond(&prepared, &sent[0].response, &context.env)? {
+      info!("Answering Digest challenge");
+      sent.push(dispatch(request, context, retry));
+    }
+  }
+
+  if let Some(last) = sent.last() {
+    parsing::apply_parsing_rules(&request.parsing_rules, &last.response.body, &mut context.env);
+  }
+  Ok(sent)
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use super::*;
+  use crate::{
+    auth::digest::DigestAuth,
+    components::home::server::HttpMethod,
+    http::test_server::{json_response, serve},
+  };
+
+  #[test]
+  fn test_status_trigger() {
+    let trigger = RefreshTrigger::default();
+    assert!(trigger.matches(&ReqResponse { status: Some(401), ..Default::default() }));
+    assert!(!trigger.matches(&ReqResponse { status: Some(200), ..Default::default() }));
+  }
+
+  #[test]
+  fn test_trigger_serialization() {
+    let json = r#"{ "request_id": "a", "trigger": { "jq": ".expired" } }"#;
+    let config: RefreshConfig = serde_json::from_str(json).unwrap();
+    assert_eq!(config.trigger, RefreshTrigger::Jq(String::from(".expired")));
+    let config: RefreshConfig = serde_json::from_str(r#"{ "request_id": "a" }"#).unwrap();
+    assert_eq!(config.trigger, RefreshTrigger::Status(401));
+  }
+
+  #[test]
+  fn test_digest_challenge_is_answered_and_nonce_counted() {
+    let challenge = "HTTP/1.1 401 Unauthorized\r\nWWW-Authenticate: Digest realm=\"cartero\", qop=\"auth\", \
+                     nonce=\"abc\", algorithm=MD5\r\nContent-Length: 0\r\nConnection: close\r\n\r\n";
+    let (base_url, server) =
+      serve(vec![challenge.to_string(), json_response("200 OK", "{}"), json_response("200 OK", "{}")]);
+    let request = RequestInput {
+      path: String::from("/device"),
+      auth: Some(Auth::Digest(DigestAuth { username: String::from("admin"), password: String::from("admin") })),
+      ..Default::default()
+    };
+    let mut context = Context { server: base_url, ..Default::default() };
+
+    let sent = send_with_refresh(&request, &mut context, None).unwrap().sent;
+    assert_eq!(sent.iter().map(|s| s.response.status).collect::<Vec<_>>(), vec![Some(401), Some(200)]);
+    assert!(sent[1].headers.contains("nc=00000001"));
+    // the challenge is remembered, so the next request authenticates up front
+    let sent = send_with_refresh(&request, &mut context, None).unwrap().sent;
+    assert_eq!(sent.len(), 1);
+
+    let requests = server.join().unwrap();
+    assert!(!requests[0].to_lowercase().contains("authorization"));
+    assert!(requests[1].contains("Digest username=\"admin\", realm=\"cartero\", uri=\"/device\""));
+    assert!(requests[2].contains("nc=00000002"));
+  }
+
+  #[test]
+  fn test_trigger_runs_the_refresh_request_and_retries_once() {
+    let unauthorized = json_response("401 Unauthorized", r#"{"error":"expired"}"#);
+    let refreshed = json_response("200 OK", r#"{"access_token":"new"}"#);
+    let (base_url, server) = serve(vec![unauthorized.clone(), refreshed, unauthorized]);
+    let env = HashMap::from([(String::from("token"), String::from("old"))]);
+    let mut context = Context { server: base_url, env, ..Default::default() };
+    let request = RequestInput {
+      id: String::from("orders"),
+      path: String::from("/orders"),
+      auth: Some(Auth::Bearer { token: String::from("{{token}}") }),
+      ..Default::default()
+    };
+    let refresh_request = RequestInput {
+      id: String::from("login"),
+      method: HttpMethod::POST,
+      path: String::from("/token"),
+      parsing_rules: String::from("token -> /access_token"),
+      ..Default::default()
+    };
+
+    let refresh = Some((&refresh_request, &RefreshTrigger::default()));
+    let sent = send_with_refresh(&request, &mut context, refresh).unwrap().sent;
+    // the retry is not refreshed again, even though it hits the trigger too
+    assert_eq!(sent.iter().map(|s| s.response.status).collect::<Vec<_>>(), vec![Some(401), Some(200), Some(401)]);
+    assert_eq!(context.env.get("token").unwrap(), "new");
+
+    let requests = server.join().unwrap();
+    assert!(requests[0].to_lowercase().contains("authorization: bearer old"));
+    assert!(requests[1].starts_with("POST /token"));
+    assert!(requests[2].starts_with("GET /orders") && requests[2].to_lowercase().contains("authorization: bearer new"));
+  }
+
+  #[test]
+  fn test_failed_refresh_keeps_the_response_that_triggered_it() {
+    let (base_url, server) = serve(vec![json_response("401 Unauthorized", r#"{"error":"expired"}"#)]);
+    let mut context = Context { server: base_url, ..Default::default() };
+    let request = RequestInput { id: String::from("orders"), path: String::from("/orders"), ..Default::default() };
+    let refresh_request = RequestInput {
+      id: String::from("login"),
+      path: String::from("/token/{{tenant}}"),
+      parsing_rules: String::from("token -> /access_token"),
+      ..Default::default()
+    };
+
+    let refresh = Some((&refresh_request, &RefreshTrigger::default()));
+    let outcome = send_with_refresh(&request, &mut context, refresh).unwrap();
+    server.join().unwrap();
+    assert_eq!(outcome.sent.iter().map(|s| s.response.status).collect::<Vec<_>>(), vec![Some(401)]);
+    assert!(matches!(AppError::from(outcome.refresh_error.unwrap()), AppError::UnresolvedVariables(_)));
+  }
+
+  #[test]
+  fn test_refresh_runs_first_when_the_env_lacks_its_variables() {
+    let (base_url, server) =
+      serve(vec![json_response("200 OK", r#"{"access_token":"tok"}"#), json_response("200 OK", "[]")]);
+    let mut context = Context { server: base_url, ..Default::default() };
+    let request = RequestInput {
+      id: String::from("orders"),
+      path: String::from("/orders"),
+      auth: Some(Auth::Bearer { token: String::from("{{token}}") }),
+      headers: String::from("X-Token: {{token}}"),
+      ..Default::default()
+    };
+    let refresh_request = RequestInput {
+      id: String::from("login"),
+      path: String::from("/token"),
+      parsing_rules: String::from("token -> /access_token"),
+      ..Default::default()
+    };
+
+    let refresh = Some((&refresh_request, &RefreshTrigger::default()));
+    let sent = send_with_refresh(&request, &mut context, refresh).unwrap().sent;
+    assert_eq!(sent.iter().map(|s| s.response.status).collect::<Vec<_>>(), vec![Some(200), Some(200)]);
+    let requests = server.join().unwrap();
+    assert!(requests[0].starts_with("GET /token"));
+    assert!(requests[1].to_lowercase().contains("x-token: tok"));
+
+    // variables the refresh request does not set are still an error
+    let request = RequestInput { path: String::from("/orders/{{order_id}}"), ..request };
+    let error = send_with_refresh(&request, &mut context, refresh);
+    assert!(matches!(AppError::from(error.err().unwrap()), AppError::UnresolvedVariables(_)));
+  }
+}
@@ -0,0 +1,297 @@
+use std::{cmp::Reverse, collections::HashMap, fs, io};
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+const COOKIES_PATH: &str = "./cartero_cookies.json";
+
+/// A stored cookie, following the RFC 6265 storage model.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct Cookie {
+  pub name: String,
+  pub value: String,
+  pub domain: String,
+  /// Only sent to exactly `domain`, not its subdomains, because the server did not set a Domain attribute
+  #[serde(default)]
+  pub host_only: bool,
+  pub path: String,
+  /// `None` for session cookies
+  #[serde(default)]
+  pub expires: Option<DateTime<Utc>>,
+  #[serde(default)]
+  pub secure: bool,
+  #[serde(default)]
+  pub http_only: bool,
+}
+
+impl Cookie {
+  /// Parses a `Set-Cookie` value received from `url`. Returns `None` when the cookie must be ignored, e.g. when its
+  /// Domain attribute does not cover the request host.
+  pub fn parse(set_cookie: &str, url: &url::Url, now: DateTime<Utc>) -> Option<Cookie> {
+    let host = url.host_str()?.to_lowercase();
+    let mut parts = set_cookie.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+      return None;
+    }
+
+    let mut cookie = Cookie {
+      name: name.to_string(),
+      value: value.trim().trim_matches('"').to_string(),
+      domain: host.clone(),
+      host_only: true,
+      path: default_path(url.path()),
+      expires: None,
+      secure: false,
+      http_only: false,
+    };
+    let mut max_age = None;
+    for attribute in parts {
+      let (key, value) = attribute.split_once('=').unwrap_or((attribute, ""));
+      let value = value.trim();
+      match key.trim().to_lowercase().as_str() {
+        "expires" => cookie.expires = cookie.expires.or_else(|| parse_date(value)),
+        "max-age" => max_age = value.parse::<i64>().ok(),
+        "domain" if !value.trim_start_matches('.').is_empty() => {
+          let domain = value.trim_start_matches('.').to_lowercase();
+          if !domain_match(&host, &domain) {
+            return None;
+          }
+          cookie.domain = domain;
+          cookie.host_only = false;
+        },
+        "path" if value.starts_with('/') => cookie.path = value.to_string(),
+        "secure" => cookie.secure = true,
+        "httponly" => cookie.http_only = true,
+        _ => {},
+      }
+    }
+    // Max-Age wins over Expires
+    if let Some(seconds) = max_age {
+      cookie.expires = Some(if seconds <= 0 { DateTime::<Utc>::MIN_UTC } else { now + Duration::seconds(seconds) });
+    }
+    Some(cookie)
+  }
+
+  /// Serializes the cookie back into a `Set-Cookie` value, so it can be edited as text and parsed again.
+  pub fn to_set_cookie(&self) -> String {
+    let mut value = format!("{}={}; Path={}", self.name, self.value, self.path);
+    if !self.host_only {
+      value.push_str(&format!("; Domain={}", self.domain));
+    }
+    if let Some(expires) = self.expires {
+      value.push_str(&format!("; Expires={}", expires.format("%a, %d %b %Y %H:%M:%S GMT")));
+    }
+    if self.secure {
+      value.push_str("; Secure");
+    }
+    if self.http_only {
+      value.push_str("; HttpOnly");
+    }
+    value
+  }
+
+  pub fn is_expired(&self, now: DateTime<Utc>) -> bool {
+    self.expires.map(|expires| expires <= now).unwrap_or(false)
+  }
+
+  /// Whether this cookie goes with a request to `url`.
+  pub fn matches(&self, url: &url::Url, now: DateTime<Utc>) -> bool {
+    let host = match url.host_str() {
+      Some(host) => host.to_lowercase(),
+      None => return false,
+    };
+    let domain_ok = if self.host_only { host == self.domain } else { domain_match(&host, &self.domain) };
+    domain_ok
+      && path_match(url.path(), &self.path)
+      && (!self.secure || url.scheme() == "https")
+      && !self.is_expired(now)
+  }
+}
+
+fn parse_date(value: &str) -> Option<DateTime<Utc>> {
+  if let Ok(date) = DateTime::parse_from_rfc2822(value) {
+    return Some(date.with_timezone(&Utc));
+  }
+  ["%a, %d-%b-%Y %H:%M:%S GMT", "%a, %d-%b-%y %H:%M:%S GMT", "%A, %d-%b-%y %H:%M:%S GMT"]
+    .iter()
+    .find_map(|format| NaiveDateTime::parse_from_str(value, format).ok())
+    .map(|date| DateTime::from_utc(date, Utc))
+}
+
+/// RFC 6265 section 5.1.3.
+fn domain_match(host: &str, domain: &str) -> bool {
+  host == domain || (host.ends_with(&format!(".{}", domain)) && host.parse::<std::net::IpAddr>().is_err())
+}
+
+/// RFC 6265 section 5.1.4.
+fn default_path(request_path: &str) -> String {
+  match request_path.rfind('/') {
+    Some(0) | None => String::from("/"),
+    Some(idx) => request_path[..idx].to_string(),
+  }
+}
+
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+  request_path == cookie_path
+    || (request_path.starts_with(cookie_path)
+      && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/')))
+}
+
+/// The cookies of one environment.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+pub struct CookieJar {
+  cookies: Vec<Cookie>,
+}
+
+impl CookieJar {
+  pub fn cookies(&self) -> &[Cookie] {
+    &self.cookies
+  }
+
+  /// Stores a cookie, replacing the one with the same name, domain and path. Expired cookies delete it instead.
+  pub fn insert(&mut self, cookie: Cookie, now: DateTime<Utc>) {
+    self.remove(&cookie.domain, &cookie.path, &cookie.name);
+    if !cookie.is_expired(now) {
+      self.cookies.push(cookie);
+    }
+  }
+
+  pub fn remove(&mut self, domain: &str, path: &str, name: &str) {
+    self.cookies.retain(|c| !(c.domain == domain && c.path == path && c.name == name));
+  }
+
+  pub fn clear_domain(&mut self, domain: &str) {
+    self.cookies.retain(|c| c.domain != domain);
+  }
+
+  /// Stores every `Set-Cookie` value of a response to `url`.
+  pub fn store_response<'a>(
+    &mut self,
+    url: &url::Url,
+    set_cookies: impl Iterator<Item = &'a str>,
+    now: DateTime<Utc>,
+  ) {
+    for set_cookie in set_cookies {
+      if let Some(cookie) = Cookie::parse(set_cookie, url, now) {
+        self.insert(cookie, now);
+      }
+    }
+  }
+
+  /// The `Cookie` header value for a request to `url`, longest paths first.
+  pub fn header_for(&self, url: &url::Url, now: DateTime<Utc>) -> Option<String> {
+    let mut matching: Vec<&Cookie> = self.cookies.iter().filter(|c| c.matches(url, now)).collect();
+    if matching.is_empty() {
+      return None;
+    }
+    matching.sort_by_key(|c| Reverse(c.path.len()));
+    Some(matching.iter().map(|c| format!("{}={}", c.name, c.value)).collect::<Vec<_>>().join("; "))
+  }
+}
+
+/// Cookie jars persisted to disk, one per environment (the active server).
+#[derive(Serialize, Deserialize, Default)]
+pub struct CookieRepository {
+  jars: HashMap<String, CookieJar>,
+}
+
+impl CookieRepository {
+  pub fn new() -> Self {
+    match fs::read_to_string(COOKIES_PATH) {
+      Ok(content) => serde_json::from_str(&content).unwrap_or_else(|e| {
+        error!("Could not read cookies: {:?}", e);
+        CookieRepository::default()
+      }),
+      Err(_) => CookieRepository::default(),
+    }
+  }
+
+  pub fn get_jar(&self, environment: &str) -> CookieJar {
+    self.jars.get(environment).cloned().unwrap_or_default()
+  }
+
+  pub fn set_jar(&mut self, environment: &str, jar: CookieJar) -> io::Result<()> {
+    self.jars.insert(environment.to_string(), jar);
+    self.save()
+  }
+
+  fn save(&self) -> io::Result<()> {
+    fs::write(COOKIES_PATH, serde_json::to_string_pretty(self)?)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use chrono::TimeZone;
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  fn url(value: &str) -> url::Url {
+    url::Url::parse(value).unwrap()
+  }
+
+  fn now() -> DateTime<Utc> {
+    Utc.with_ymd_and_hms(2024, 1, 1, 0, 0, 0).unwrap()
+  }
+
+  #[test]
+  fn test_parse_set_cookie() {
+    let cookie = Cookie::parse(
+      "sid=abc; Domain=.example.com; Path=/admin; Expires=Wed, 21 Oct 2026 07:28:00 GMT; Secure; HttpOnly",
+      &url("https://api.example.com/admin/login"),
+      now(),
+    )
+    .unwrap();
+    assert_eq!(cookie.domain, "example.com");
+    assert!(!cookie.host_only);
+    assert_eq!(cookie.path, "/admin");
+    assert_eq!(cookie.expires, Some(Utc.with_ymd_and_hms(2026, 10, 21, 7, 28, 0).unwrap()));
+    assert!(cookie.secure && cookie.http_only);
+
+    let cookie = Cookie::parse("theme=dark; Max-Age=60", &url("http://example.com/a/b"), now()).unwrap();
+    assert!(cookie.host_only);
+    assert_eq!(cookie.path, "/a");
+    assert_eq!(cookie.expires, Some(now() + Duration::seconds(60)));
+
+    // a server cannot set cookies for a domain it does not belong to
+    assert_eq!(Cookie::parse("sid=abc; Domain=other.com", &url("http://example.com/"), now()), None);
+  }
+
+  #[test]
+  fn test_header_for_applies_domain_path_and_secure_rules() {
+    let mut jar = CookieJar::default();
+    let login = url("https://example.com/admin/login");
+    let set_cookies = ["sid=abc; Path=/admin; Secure", "lang=en; Domain=example.com; Path=/", "host=1"];
+    jar.store_response(&login, set_cookies.into_iter(), now());
+
+    let header = |value: &str| jar.header_for(&url(value), now());
+    assert_eq!(header("https://example.com/admin/users"), Some(String::from("sid=abc; host=1; lang=en")));
+    assert_eq!(header("http://example.com/admin/users"), Some(String::from("host=1; lang=en")));
+    assert_eq!(header("https://api.example.com/administrator"), Some(String::from("lang=en")));
+    assert_eq!(header("https://example.org/"), None);
+  }
+
+  #[test]
+  fn test_set_cookie_round_trip() {
+    let site = url("https://api.example.com/");
+    let set_cookie =
+      "sid=abc; Path=/admin; Domain=example.com; Expires=Wed, 21 Oct 2026 07:28:00 GMT; Secure; HttpOnly";
+    let cookie = Cookie::parse(set_cookie, &site, now()).unwrap();
+    assert_eq!(cookie.to_set_cookie(), set_cookie);
+    let host_only = Cookie::parse("theme=dark", &site, now()).unwrap();
+    assert_eq!(Cookie::parse(&host_only.to_set_cookie(), &site, now()), Some(host_only));
+  }
+
+  #[test]
+  fn test_expired_cookie_deletes_stored_one() {
+    let mut jar = CookieJar::default();
+    let site = url("http://example.com/");
+    jar.store_response(&site, ["sid=abc"].into_iter(), now());
+    jar.store_response(&site, ["sid=; Max-Age=0"].into_iter(), now());
+    assert!(jar.cookies().is_empty());
+  }
+}
@@ -0,0 +1,270 @@
+use std::{
+  fs::{self, OpenOptions},
+  io::{self, Write},
+};
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tracing::error;
+
+use super::local_storage::RequestInput;
+use crate::{
+  auth::{Secrets, REDACTED},
+  http::{dispatch::Sent, redirect::Redirect, timing::Timing, ReqResponse, PLACEHOLDER},
+};
+
+const HISTORY_PATH: &str = "./cartero_history.jsonl";
+/// Headers whose values are credentials whatever the auth, lowercase.
+const SECRET_HEADERS: [&str; 5] =
+  ["authorization", "proxy-authorization", "cookie", "set-cookie", "x-amz-security-token"];
+
+/// One sent request, appended as a single JSON line to the history file.
+#[derive(Serialize, Deserialize, Clone)]
+pub struct HistoryEntry {
+  pub timestamp: DateTime<Local>,
+  pub request: RequestInput,
+  pub url: String,
+  /// Headers as they went over the wire, with the credentials redacted
+  #[serde(default)]
+  pub sent_headers: String,
+  pub status: Option<u16>,
+  pub headers: String,
+  pub body: String,
+  #[serde(default)]
+  pub body_truncated: bool,
+  #[serde(default)]
+  pub size: usize,
+  pub elapsed_ms: u64,
+  #[serde(default)]
+  pub proxy: Option<String>,
+  #[serde(default)]
+  pub timing: Option<Timing>,
+  /// Redirects before the response, with their bodies truncated like the response's
+  #[serde(default)]
+  pub redirects: Vec<Redirect>,
+}
+
+impl HistoryEntry {
+  /// The entry for `sent`. Credentials are redacted from the url, the request and response headers and the request's
+  /// auth, as the history is saved in plain text.
+  pub fn new(sent: &Sent, max_body_bytes: usize) -> Self {
+    let (response, secrets) = (&sent.response, &sent.secrets);
+    let (body, body_truncated) = truncate(&response.body, max_body_bytes);
+    let request = RequestInput {
+      headers: redact_headers(&sent.request.headers, secrets),
+      auth: sent.request.auth.as_ref().map(|auth| auth.redacted()),
+      ..sent.request.clone()
+    };
+    HistoryEntry {
+      timestamp: Local::now(),
+      request,
+      url: redact_query(&sent.url, secrets),
+      sent_headers: redact_headers(&sent.headers, secrets),
+      status: response.status,
+      headers: redact_headers(&response.headers, secrets),
+      body,
+      body_truncated,
+      size: response.body.len(),
+      elapsed_ms: response.elapsed_ms,
+      proxy: response.proxy.clone(),
+      timing: response.timing.clone(),
+      redirects: response
+        .redirects
+        .iter()
+        .map(|hop| {
+          let body = truncate(&hop.response.body, max_body_bytes).0;
+          let url = redact_query(&hop.url, secrets);
+          let headers = redact_headers(&hop.response.headers, secrets);
+          Redirect { url, response: ReqResponse { body, headers, ..hop.response.clone() }, ..hop.clone() }
+        })
+        .collect(),
+    }
+  }
+
+  /// The request to send again. Credentials redacted from it are taken from `saved`, the request it was sent from,
+  /// when that still has them.
+  pub fn replay(&self, saved: Option<&RequestInput>) -> RequestInput {
+    let mut request = self.request.clone();
+    if let Some(saved) = saved {
+      if request.auth.is_some() && request.auth == saved.auth.as_ref().map(|auth| auth.redacted()) {
+        request.auth = saved.auth.clone();
+      }
+      if request.headers.contains(REDACTED) {
+        request.headers = saved.headers.clone();
+      }
+    }
+    request
+  }
+
+  pub fn to_response(&self) -> ReqResponse {
+    ReqResponse {
+      status: self.status,
+      headers: self.headers.clone(),
+      body: self.body.clone(),
+      elapsed_ms: self.elapsed_ms,
+      proxy: self.proxy.clone(),
+      timing: self.timing.clone(),
+      redirects: self.redirects.clone(),
+      ..Default::default()
+    }
+  }
+}
+
+/// `Name: value` lines with the values of credential headers redacted, unless they only refer to env variables.
+/// Response headers have their names quoted.
+fn redact_headers(headers: &str, secrets: &Secrets) -> String {
+  let is_secret = |name: &str, value: &str| {
+    let name = name.trim().trim_matches('"').to_lowercase();
+    let secret = SECRET_HEADERS.contains(&name.as_str()) || secrets.headers.contains(&name);
+    secret && !PLACEHOLDER.replace_all(value, "").trim().is_empty()
+  };
+  let lines = headers.lines().map(|line| match line.split_once(':') {
+    Some((name, value)) if is_secret(name, value) => format!("{}: {}", name, REDACTED),
+    _ => line.to_string(),
+  });
+  lines.collect::<Vec<_>>().join("\n")
+}
+
+/// `url` with the values of the secret query parameters redacted.
+fn redact_query(url: &str, secrets: &Secrets) -> String {
+  let mut parsed = match url::Url::parse(url) {
+    Ok(parsed) if parsed.query_pairs().any(|(name, _)| secrets.query.iter().any(|secret| *secret == name)) => parsed,
+    _ => return url.to_string(),
+  };
+  let pairs: Vec<(String, String)> = parsed
+    .query_pairs()
+    .map(|(name, value)| {
+      let value = if secrets.query.iter().any(|secret| *secret == name) { REDACTED.into() } else { value };
+      (name.to_string(), value.to_string())
+    })
+    .collect();
+  parsed.query_pairs_mut().clear().extend_pairs(pairs);
+  parsed.to_string()
+}
+
+fn truncate(body: &str, max_bytes: usize) -> (String, bool) {
+  if body.len() <= max_bytes {
+    return (body.to_string(), false);
+  }
+  let mut end = max_bytes;
+  while !body.is_char_boundary(end) {
+    end -= 1;
+  }
+  (body[..end].to_string(), true)
+}
+
+pub struct HistoryRepository {
+  path: String,
+  entries: Vec<HistoryEntry>,
+}
+
+impl Default for HistoryRepository {
+  fn default() -> Self {
+    HistoryRepository { path: String::from(HISTORY_PATH), entries: Vec::new() }
+  }
+}
+
+impl HistoryRepository {
+  pub fn new() -> Self {
+    HistoryRepository::open(HISTORY_PATH)
+  }
+
+  fn open(path: &str) -> Self {
+    let entries = match fs::read_to_string(path) {
+      Ok(content) => content
+        .lines()
+        .filter(|line| !line.trim().is_empty())
+        .filter_map(|line| match serde_json::from_str::<HistoryEntry>(line) {
+          Ok(entry) => Some(entry),
+          Err(e) => {
+            error!("Skipping history line: {:?}", e);
+            None
+          },
+        })
+        .collect(),
+      Err(_) => Vec::new(),
+    };
+    HistoryRepository { path: path.to_string(), entries }
+  }
+
+  pub fn append(&mut self, entry: HistoryEntry) -> io::Result<()> {
+    let mut file = OpenOptions::new().create(true).append(true).open(&self.path)?;
+    writeln!(file, "{}", serde_json::to_string(&entry)?)?;
+    self.entries.push(entry);
+    Ok(())
+  }
+
+  /// Entries recorded for the saved request with the given id, newest first.
+  pub fn entries_for(&self, request_id: &str) -> Vec<&HistoryEntry> {
+    self.entries.iter().rev().filter(|entry| entry.request.id == request_id).collect()
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use std::collections::HashMap;
+
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::{
+    auth::{ApiKeyLocation, Auth},
+    http::{self, test_server::{json_response, serve}, Context},
+    refresh,
+  };
+
+  #[test]
+  fn test_credentials_never_reach_the_file() {
+    let login = "HTTP/1.1 302 Found\r\nLocation: /me\r\nSet-Cookie: sid=l0gin; Path=/\r\n\
+                 Content-Length: 0\r\nConnection: close\r\n\r\n";
+    let me = "HTTP/1.1 200 OK\r\nSet-Cookie: theme=d4rk\r\nContent-Length: 2\r\nConnection: close\r\n\r\n{}";
+    let (base_url, server) = serve(vec![login.to_string(), me.to_string(), json_response("200 OK", "{}")]);
+    let env = HashMap::from([(String::from("key"), String::from("k3y"))]);
+    let mut context = Context { server: base_url, env, ..Default::default() };
+    let request = RequestInput {
+      path: String::from("/login"),
+      headers: String::from("Cookie: sid=s3ssion\nAccept: application/json"),
+      auth: Some(Auth::Bearer { token: String::from("t0ken") }),
+      ..Default::default()
+    };
+    let api_key = Auth::ApiKey {
+      key: String::from("api_key"),
+      value: String::from("{{key}}"),
+      location: ApiKeyLocation::Query,
+    };
+    let query_key = RequestInput { auth: Some(api_key.clone()), ..request.clone() };
+    let mut sent = refresh::send_with_refresh(&request, &mut context, None).unwrap().sent;
+    sent.extend(refresh::send_with_refresh(&query_key, &mut context, None).unwrap().sent);
+    server.join().unwrap();
+    assert!(sent[0].headers.contains("Bearer t0ken"));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("history.jsonl");
+    let mut history = HistoryRepository::open(path.to_str().unwrap());
+    for exchange in &sent {
+      history.append(HistoryEntry::new(exchange, 1024)).unwrap();
+    }
+    let saved = fs::read_to_string(&path).unwrap();
+    for secret in ["t0ken", "s3ssion", "k3y", "l0gin", "d4rk"] {
+      assert!(!saved.contains(secret), "{} was saved", secret);
+    }
+    let entries = HistoryRepository::open(path.to_str().unwrap()).entries;
+    assert!(entries[0].sent_headers.contains(&format!("authorization: {}", REDACTED)));
+    assert_eq!(entries[0].request.auth, Some(Auth::Bearer { token: String::from(REDACTED) }));
+    assert_eq!(entries[0].request.headers, format!("Cookie: {}\nAccept: application/json", REDACTED));
+    assert!(entries[0].headers.contains(&format!("\"set-cookie\": {}", REDACTED)));
+    assert!(entries[0].redirects[0].response.headers.contains(&format!("\"set-cookie\": {}", REDACTED)));
+    assert!(entries[1].url.ends_with("api_key=%5Bredacted%5D"));
+    // references to the env are kept, as they hold no secret
+    assert_eq!(entries[1].request.auth, Some(api_key));
+
+    let replayed = entries[0].replay(Some(&request));
+    assert_eq!((replayed.auth, replayed.headers), (request.auth.clone(), request.headers.clone()));
+  }
+
+  #[test]
+  fn test_truncate_respects_char_boundaries() {
+    assert_eq!(truncate("hello", 10), (String::from("hello"), false));
+    assert_eq!(truncate("héllo", 2), (String::from("h"), true));
+  }
+}
@@ -1,16 +1,24 @@
 use std::sync::OnceLock;
-use std::{collections::HashMap, fs};
+use std::{collections::HashMap, fs, io};
 
 use ratatui::widgets::ListState;
 use serde::{Deserialize, Serialize};
 use tracing::{debug, error};
 
-use crate::components::home::server;
+use crate::{
+  auth::Auth,
+  components::home::server,
+  http::{client::ClientSettings, proxy::ProxySettings, tls::TlsSettings, Context},
+  refresh::{RefreshConfig, RefreshTrigger},
+};
 
 const DB_PATH: &str = "./cartero.json";
 
 #[derive(Serialize, Deserialize, Clone)]
 pub struct RequestInput {
+  /// Empty for requests saved without one, until [`LocalStorageRepository::new`] assigns it
+  #[serde(default)]
+  pub id: String,
   pub method: server::HttpMethod,
   pub server: String,
   pub path: String,
@@ -19,11 +27,23 @@ pub struct RequestInput {
   pub headers: String,
   #[serde(default = "emtpy_string")]
   pub parsing_rules: String,
+  #[serde(default = "emtpy_string")]
+  pub snapshot_ignore: String,
+  /// Overrides the collection's default auth when set
+  #[serde(default)]
+  pub auth: Option<Auth>,
+  /// Whether the environment's cookie jar is sent with, and updated by, this request
+  #[serde(default = "enabled")]
+  pub cookies: bool,
+  /// Overrides the global client settings field by field
+  #[serde(default)]
+  pub client: ClientSettings,
 }
 
 impl Default for RequestInput {
   fn default() -> Self {
     RequestInput {
+      id: new_request_id(),
       method: server::HttpMethod::GET,
       server: String::from("http://localhost"),
       path: String::new(),
@@ -31,10 +51,22 @@ impl Default for RequestInput {
       payload: String::new(),
       headers: String::new(),
       parsing_rules: String::new(),
+      snapshot_ignore: String::new(),
+      auth: None,
+      cookies: true,
+      client: ClientSettings::default(),
     }
   }
 }
 
+fn enabled() -> bool {
+  true
+}
+
+fn new_request_id() -> String {
+  format!("{:016x}", rand::random::<u64>())
+}
+
 fn emtpy_string() -> String {
   String::from("")
 }
@@ -43,6 +75,20 @@ fn emtpy_string() -> String {
 pub struct LocalStorageRepository {
   #[serde(default = "default_env")]
   env: HashMap<String, String>,
+  #[serde(default)]
+  refresh: Option<RefreshConfig>,
+  /// Default auth for every request that does not set its own
+  #[serde(default)]
+  auth: Option<Auth>,
+  /// Proxies per environment, keyed by server
+  #[serde(default)]
+  proxies: HashMap<String, ProxySettings>,
+  /// TLS settings per environment, keyed by server
+  #[serde(default)]
+  tls: HashMap<String, TlsSettings>,
+  /// `host:port -> address` mappings per environment, keyed by server
+  #[serde(default)]
+  resolve: HashMap<String, Vec<String>>,
   servers: Servers,
   requests: Requests,
 }
@@ -53,13 +99,24 @@ fn default_env() -> HashMap<String, String> {
 
 impl LocalStorageRepository {
   pub fn new() -> LocalStorageRepository {
-    match fs::read_to_string(DB_PATH) {
+    LocalStorageRepository::load(DB_PATH)
+  }
+
+  /// Reads the collection at `path`. Requests saved without an id get one, and the file is written back right away,
+  /// as history entries and the refresh config refer to requests by id and must still find them after a restart.
+  fn load(path: &str) -> LocalStorageRepository {
+    let mut data = match fs::read_to_string(path) {
       Ok(db_content) => match serde_json::from_str::<LocalStorageRepository>(&db_content) {
         Ok(data) => data,
         Err(e) => {
           error!("{:?}", e);
           LocalStorageRepository {
             env: default_env(),
+            refresh: None,
+            auth: None,
+            proxies: HashMap::new(),
+            tls: HashMap::new(),
+            resolve: HashMap::new(),
             servers: Servers { value: vec![String::from("http://localhost")], active: 0 },
             requests: Requests::default(),
           }
@@ -69,15 +126,26 @@ impl LocalStorageRepository {
         error!("{:?}", e);
         LocalStorageRepository {
           env: default_env(),
+          refresh: None,
+          auth: None,
+          proxies: HashMap::new(),
+          tls: HashMap::new(),
+          resolve: HashMap::new(),
           servers: Servers { value: vec![String::from("http://localhost")], active: 0 },
           requests: Requests::default(),
         }
       },
+    };
+    if data.requests.assign_missing_ids() {
+      if let Err(e) = write_db(path, &data) {
+        error!("Could not save the new request ids: {:?}", e);
+      }
     }
+    data
   }
 
-  pub fn save(&self) {
-    write_db(self.clone());
+  pub fn save(&self) -> io::Result<()> {
+    write_db(DB_PATH, self)
   }
 
   pub fn get_method(&self) -> server::HttpMethod {
@@ -100,6 +168,74 @@ impl LocalStorageRepository {
     self.servers.clear();
   }
 
+  pub fn get_env(&self) -> HashMap<String, String> {
+    self.env.clone()
+  }
+
+  pub fn set_env(&mut self, env: HashMap<String, String>) {
+    self.env = env;
+  }
+
+  /// Everything a request from this collection is sent with, besides the request itself.
+  pub fn get_context(&self) -> Context {
+    let server = self.get_server();
+    let proxy = self.proxies.get(&server).cloned().unwrap_or_default();
+    let tls = self.tls.get(&server).cloned().unwrap_or_default();
+    let resolve = self.resolve.get(&server).cloned().unwrap_or_default();
+    let (env, default_auth) = (self.env.clone(), self.auth.clone());
+    Context { server, env, default_auth, proxy, tls, resolve, ..Default::default() }
+  }
+
+  /// Whether the active environment skips certificate verification.
+  pub fn is_insecure(&self) -> bool {
+    self.tls.get(&self.get_server()).map(|tls| tls.insecure).unwrap_or(false)
+  }
+
+  /// The auth the active request is sent with, after falling back to the collection default.
+  pub fn get_effective_auth(&self) -> Option<Auth> {
+    self.requests.get_active().auth.or_else(|| self.auth.clone())
+  }
+
+  /// The refresh request and its trigger, if one is set and still exists.
+  pub fn get_refresh(&self) -> Option<(RequestInput, RefreshTrigger)> {
+    let refresh = self.refresh.as_ref()?;
+    let request = self.requests.value.iter().find(|r| r.id == refresh.request_id)?;
+    Some((request.clone(), refresh.trigger.clone()))
+  }
+
+  pub fn is_refresh_request(&self, request_id: &str) -> bool {
+    self.refresh.as_ref().map(|r| r.request_id == request_id).unwrap_or(false)
+  }
+
+  /// Marks the active request as the refresh request, or unmarks it if it already is.
+  pub fn toggle_refresh_request(&mut self) {
+    let request_id = self.requests.get_active().id;
+    if self.is_refresh_request(&request_id) {
+      self.refresh = None;
+    } else {
+      let trigger = self.refresh.take().map(|r| r.trigger).unwrap_or_default();
+      self.refresh = Some(RefreshConfig { request_id, trigger });
+    }
+  }
+
+  pub fn toggle_cookies(&mut self) {
+    let request = self.requests.get_active_mut();
+    request.cookies = !request.cookies;
+  }
+
+  /// Makes the active request stop at the first redirect, or follow them again.
+  pub fn toggle_redirects(&mut self) {
+    let client = &mut self.requests.get_active_mut().client;
+    client.max_redirects = match client.max_redirects {
+      Some(0) => None,
+      _ => Some(0),
+    };
+  }
+
+  pub fn get_active_request(&self) -> RequestInput {
+    self.requests.get_active()
+  }
+
   pub fn get_query(&self) -> String {
     self.requests.get_active().query
   }
@@ -189,7 +325,8 @@ impl LocalStorageRepository {
   }
 
   pub fn duplicate_request(&mut self) {
-    self.requests.add(self.requests.get_active());
+    let request = RequestInput { id: new_request_id(), ..self.requests.get_active() };
+    self.requests.add(request);
   }
 
   pub fn next_request(&mut self) {
@@ -204,27 +341,7 @@ impl LocalStorageRepository {
 impl Default for LocalStorageRepository {
   fn default() -> Self {
     debug!("Starting LocalStorageRepository");
-    match fs::read_to_string(DB_PATH) {
-      Ok(db_content) => match serde_json::from_str::<LocalStorageRepository>(&db_content) {
-        Ok(data) => data,
-        Err(e) => {
-          error!("{:?}", e);
-          LocalStorageRepository {
-            env: default_env(),
-            servers: Servers { value: vec![String::from("http://localhost")], active: 0 },
-            requests: Requests::default(),
-          }
-        },
-      },
-      Err(e) => {
-        error!("{:?}", e);
-        LocalStorageRepository {
-          env: default_env(),
-          servers: Servers { value: vec![String::from("http://localhost")], active: 0 },
-          requests: Requests::default(),
-        }
-      },
-    }
+    LocalStorageRepository::load(DB_PATH)
   }
 }
 
@@ -241,6 +358,16 @@ impl Default for Requests {
 }
 
 impl Requests {
+  /// Gives the requests saved without an id a new one, returning whether there were any.
+  fn assign_missing_ids(&mut self) -> bool {
+    let mut assigned = false;
+    for request in self.value.iter_mut().filter(|request| request.id.is_empty()) {
+      request.id = new_request_id();
+      assigned = true;
+    }
+    assigned
+  }
+
   fn get_active(&self) -> RequestInput {
     if self.value.len() > 0 {
       self.value[self.active].clone()
@@ -407,8 +534,30 @@ impl Servers {
 
 use serde_json::to_string_pretty;
 
-fn write_db(data: LocalStorageRepository) {
-  let serialized_data = to_string_pretty(&data).expect("Can be serialized");
+fn write_db(path: &str, data: &LocalStorageRepository) -> io::Result<()> {
+  let serialized_data = to_string_pretty(data)?;
   debug!("Serialized data: {}", serialized_data); // Print the serialized data
-  fs::write(DB_PATH, serialized_data).expect("Can write to database");
+  fs::write(path, serialized_data)
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+
+  #[test]
+  fn test_ids_generated_on_load_survive_a_restart() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cartero.json");
+    let path = path.to_str().unwrap();
+    let mut saved = serde_json::to_value(LocalStorageRepository::load(path)).unwrap();
+    saved["requests"]["value"][0].as_object_mut().unwrap().remove("id");
+    fs::write(path, saved.to_string()).unwrap();
+
+    let first = LocalStorageRepository::load(path).get_active_request().id;
+    let second = LocalStorageRepository::load(path).get_active_request().id;
+    assert!(!first.is_empty());
+    assert_eq!(first, second);
+  }
 }
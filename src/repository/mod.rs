@@ -1 +1,3 @@
+pub mod cookies;
+pub mod history;
 pub mod local_storage;
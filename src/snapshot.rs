@@ -0,0 +1,189 @@
+use std::{fs, io, path::PathBuf};
+
+use color_eyre::eyre::{eyre, Result};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tokio::task::spawn_blocking;
+
+use crate::{
+  config::Config,
+  diff::{self, Change},
+  http::{self, ReqResponse},
+  refresh,
+  repository::local_storage::{LocalStorageRepository, RequestInput},
+};
+
+const SNAPSHOT_DIR: &str = "./snapshots";
+
+/// Golden copy of a response, stored as `snapshots/<name>.json`.
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+  pub status: Option<u16>,
+  pub body: Value,
+}
+
+impl Snapshot {
+  pub fn from_response(response: &ReqResponse) -> Self {
+    let body = serde_json::from_str::<Value>(&response.body).unwrap_or_else(|_| Value::String(response.body.clone()));
+    Snapshot { status: response.status, body }
+  }
+
+  fn to_value(&self, ignore: &[String]) -> Value {
+    let mut body = self.body.clone();
+    for pointer in ignore {
+      diff::remove_pointer(&mut body, pointer);
+    }
+    json!({ "status": self.status, "body": body })
+  }
+}
+
+/// Builds a stable file name for a saved request: its id, so the snapshot follows the request when its method or
+/// path is edited and stays apart from other requests to the same path.
+pub fn name_for(request: &RequestInput) -> String {
+  request.id.clone()
+}
+
+/// Ignore rules are written one JSON pointer per line, relative to the response body.
+pub fn ignore_rules(request: &RequestInput) -> Vec<String> {
+  request.snapshot_ignore.lines().map(|l| l.trim().to_string()).filter(|l| !l.is_empty()).collect()
+}
+
+fn path_for(name: &str) -> PathBuf {
+  PathBuf::from(SNAPSHOT_DIR).join(format!("{}.json", name))
+}
+
+pub fn load(name: &str) -> io::Result<Option<Snapshot>> {
+  match fs::read_to_string(path_for(name)) {
+    Ok(content) => serde_json::from_str(&content).map(Some).map_err(io::Error::from),
+    Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+    Err(e) => Err(e),
+  }
+}
+
+pub fn save(name: &str, snapshot: &Snapshot) -> io::Result<()> {
+  fs::create_dir_all(SNAPSHOT_DIR)?;
+  fs::write(path_for(name), serde_json::to_string_pretty(snapshot)?)
+}
+
+/// Diffs `actual` against the golden `expected` snapshot after dropping the ignored pointers from both bodies.
+pub fn compare(expected: &Snapshot, actual: &Snapshot, ignore: &[String]) -> Vec<Change> {
+  diff::json_diff(&expected.to_value(ignore), &actual.to_value(ignore))
+}
+
+pub fn format_change(change: &Change) -> String {
+  match change {
+    Change::Added { pointer, value } => format!("+ {}: {}", pointer, value),
+    Change::Removed { pointer, value } => format!("- {}: {}", pointer, value),
+    Change::Changed { pointer, old, new } => format!("~ {}: {} -> {}", pointer, old, new),
+  }
+}
+
+/// Sends every saved request and checks it against its golden snapshot, printing a report to stdout.
+/// With `update` set, the snapshots are rewritten from the fresh responses instead.
+pub async fn run(update: bool) -> Result<()> {
+  let repo = LocalStorageRepository::default();
+  let client = Config::new().map(|config| config.config.client).unwrap_or_default();
+  let mut context = http::Context { client, ..repo.get_context() };
+  let refresh = repo.get_refresh();
+  let mut failures = 0;
+
+  for request in repo.get_request_list().clone() {
+    let name = name_for(&request);
+    let label = format!("{} {}", request.method.to_string(), request.path);
+    let ignore = ignore_rules(&request);
+    let refresh = refresh.clone();
+    let (result, updated_context) = spawn_blocking(move || {
+      let refresh = refresh.as_ref().map(|(request, trigger)| (request, trigger));
+      let result = refresh::send_with_refresh(&request, &mut context, refresh);
+      (result, context)
+    })
+    .await?;
+    context = updated_context;
+    let last = result.map(|outcome| {
+      if let Some(e) = &outcome.refresh_error {
+        println!("warn {}: could not refresh the session: {}", label, e);
+      }
+      outcome.sent.into_iter().last()
+    });
+    let response = match last {
+      Ok(Some(last)) => last.response,
+      Ok(None) => continue,
+      Err(e) => {
+        println!("FAIL {}: {}", label, e);
+        failures += 1;
+        continue;
+      },
+    };
+    let actual = Snapshot::from_response(&response);
+
+    if update {
+      save(&name, &actual)?;
+      println!("saved {}", label);
+      continue;
+    }
+
+    match load(&name)? {
+      Some(expected) => {
+        let changes = compare(&expected, &actual, &ignore);
+        if changes.is_empty() {
+          println!("ok   {}", label);
+        } else {
+          println!("FAIL {}", label);
+          changes.iter().for_each(|c| println!("     {}", format_change(c)));
+          failures += 1;
+        }
+      },
+      None => {
+        println!("FAIL {}: no snapshot recorded, run with --update-snapshots", label);
+        failures += 1;
+      },
+    }
+  }
+
+  if failures > 0 {
+    return Err(eyre!("{} snapshot(s) did not match", failures));
+  }
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use pretty_assertions::assert_eq;
+
+  use super::*;
+  use crate::components::home::server::HttpMethod;
+
+  #[test]
+  fn test_compare_ignores_volatile_fields() {
+    let expected = Snapshot { status: Some(200), body: json!({ "id": "a1", "createdAt": 1, "name": "goku" }) };
+    let actual = Snapshot { status: Some(200), body: json!({ "id": "b2", "createdAt": 2, "name": "goku" }) };
+    let ignore = vec![String::from("/id"), String::from("/createdAt")];
+    assert!(compare(&expected, &actual, &ignore).is_empty());
+  }
+
+  #[test]
+  fn test_compare_reports_status_and_body_changes() {
+    let expected = Snapshot { status: Some(200), body: json!({ "name": "goku" }) };
+    let actual = Snapshot { status: Some(500), body: json!({ "name": "vegeta" }) };
+    let changes: Vec<String> = compare(&expected, &actual, &[]).iter().map(format_change).collect();
+    assert_eq!(changes, vec!["~ /body/name: \"goku\" -> \"vegeta\"", "~ /status: 200 -> 500"]);
+  }
+
+  #[test]
+  fn test_name_for_request() {
+    let request = RequestInput {
+      id: String::from("0f3a"),
+      path: String::from("/experiences/{id}/cancel"),
+      ..Default::default()
+    };
+    assert_eq!(name_for(&request), "0f3a");
+    let other = RequestInput { id: String::from("77b1"), ..request.clone() };
+    assert_ne!(name_for(&other), name_for(&request));
+    let renamed = RequestInput {
+      method: HttpMethod::POST,
+      path: String::from("/v2/experiences/{id}/cancel"),
+      ..request.clone()
+    };
+    assert_eq!(name_for(&renamed), name_for(&request));
+  }
+}
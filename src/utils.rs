@@ -157,3 +157,14 @@ Config directory: {config_dir_path}
 Data directory: {data_dir_path}"
   )
 }
+
+/// Copies `text` to the system clipboard with an OSC 52 escape sequence, which the terminal handles, also over SSH.
+pub fn copy_to_clipboard(text: &str) -> std::io::Result<()> {
+  use std::io::Write;
+
+  use base64::{engine::general_purpose::STANDARD, Engine};
+
+  let mut stdout = std::io::stdout();
+  write!(stdout, "\x1b]52;c;{}\x07", STANDARD.encode(text))?;
+  stdout.flush()
+}